@@ -1,7 +1,10 @@
+//! Measure the throughput of the indexable `DataLoader` on a dataset of images.
+#![allow(missing_docs)]
+
 use ai_dataloader::indexable::DataLoader;
-use ai_dataloader::{Dataset, GetSample, Len};
+use ai_dataloader::{Dataset, GetSample, GetSampleRef, Len};
 use criterion::{criterion_group, criterion_main, Criterion, Throughput};
-use ndarray::Array3;
+use ndarray::{Array3, ArrayView3};
 use ndarray_rand::rand_distr::Uniform;
 use ndarray_rand::RandomExt;
 
@@ -10,6 +13,7 @@ const IMAGE_SIZE: usize = 50;
 const DATASET_LEN: usize = 500;
 
 /// Dataset that return the same random image each time.
+#[derive(Debug)]
 pub struct RandomUnique {
     image: Array3<u8>,
 }
@@ -34,28 +38,51 @@ impl GetSample for RandomUnique {
     type Sample = (Array3<u8>, i32);
 
     fn get_sample(&self, index: usize) -> Self::Sample {
-        (self.image.clone(), (index % NUM_CLASS) as i32)
+        #[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
+        let label = (index % NUM_CLASS) as i32;
+        (self.image.clone(), label)
+    }
+}
+
+impl GetSampleRef for RandomUnique {
+    type SampleRef<'a> = (ArrayView3<'a, u8>, i32);
+
+    fn get_sample_ref(&self, index: usize) -> Self::SampleRef<'_> {
+        #[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
+        let label = (index % NUM_CLASS) as i32;
+        (self.image.view(), label)
     }
 }
 
 fn iter_all_dataset(loader: &DataLoader<RandomUnique>) -> usize {
     let mut num_sample = 0;
-    for (_sample, label) in loader.iter() {
+    for (_sample, label) in loader {
+        num_sample += label.len();
+    }
+    num_sample
+}
+
+fn iter_ref_all_dataset(loader: &DataLoader<RandomUnique>) -> usize {
+    let mut num_sample = 0;
+    for (_sample, label) in loader.iter_ref() {
         num_sample += label.len();
     }
     num_sample
 }
 
 fn bench(c: &mut Criterion) {
+    const BYTES: u64 = DATASET_LEN as u64 * IMAGE_SIZE as u64 * IMAGE_SIZE as u64 * 3;
+
     let loader = DataLoader::builder(RandomUnique::default())
         .batch_size(16)
         .build();
 
-    const BYTES: u64 = DATASET_LEN as u64 * IMAGE_SIZE as u64 * IMAGE_SIZE as u64 * 3;
-
     let mut group = c.benchmark_group("throughput-example");
     group.throughput(Throughput::Bytes(BYTES));
     group.bench_function("iter_all_dataset", |b| b.iter(|| iter_all_dataset(&loader)));
+    group.bench_function("iter_ref_all_dataset", |b| {
+        b.iter(|| iter_ref_all_dataset(&loader));
+    });
     group.finish();
 }
 
@@ -1,3 +1,5 @@
+//! Iterate over a tokenized text dataset with the iterable `DataLoader`.
+
 use ai_dataloader::iterable::DataLoader;
 
 #[cfg(not(feature = "tch"))]
@@ -3,6 +3,9 @@
 
 mod default_collate;
 pub use default_collate::DefaultCollate;
+mod error;
+pub(crate) use error::{common_length, map_values};
+pub use error::{CollateError, CollateErrorKind};
 
 #[cfg(feature = "tch")]
 #[cfg_attr(docsrs, doc(cfg(feature = "tch")))]
@@ -35,8 +38,92 @@ where
     }
 }
 
+/// Collate that can fail, returning a [`CollateError`] describing the samples at fault instead of panicking.
+///
+/// [`DefaultCollate`] implements it, and its [`Collate`] implementation panics with the message of the error. It's
+/// also implemented for the closures, that can't fail, and [`Infallible`] adapts any other [`Collate`].
+///
+/// Only [`DataLoader::try_iter`](crate::indexable::DataLoader::try_iter) yields the errors. The other iterators,
+/// including `iter_prefetch` and the ones of the iterable [`DataLoader`](crate::iterable::DataLoader), call
+/// [`Collate`] and panic with the message of the error.
+///
+/// ```
+/// use ai_dataloader::collate::{CollateErrorKind, DefaultCollate, TryCollate};
+/// use ndarray::array;
+///
+/// let error = DefaultCollate
+///     .try_collate(vec![(array![1, 2], 0), (array![3, 4], 1), (array![5, 6, 7], 0)])
+///     .unwrap_err();
+/// assert_eq!(error.path, ".0");
+/// assert_eq!(error.batch_indices, [2]);
+/// assert_eq!(
+///     error.kind,
+///     CollateErrorKind::ShapeMismatch { shapes: vec![vec![2], vec![2], vec![3]] }
+/// );
+/// ```
+pub trait TryCollate<T> {
+    /// The type of the collate function's output
+    type Output;
+    /// Take a batch of samples and collate them.
+    ///
+    /// # Errors
+    ///
+    /// Return the error describing why the samples can't be collated together, for instance arrays of different shapes.
+    fn try_collate(&self, batch: Vec<T>) -> Result<Self::Output, CollateError>;
+}
+
+impl<T, F, O> TryCollate<T> for F
+where
+    F: Fn(Vec<T>) -> O,
+{
+    type Output = O;
+    fn try_collate(&self, batch: Vec<T>) -> Result<Self::Output, CollateError> {
+        Ok((self)(batch))
+    }
+}
+
+/// Adapter making any [`Collate`] a [`TryCollate`] that never fails, for instance to give a user-defined collate
+/// function to the `try_collate_fn` option of the [`DataLoader`](crate::indexable::DataLoader) builder.
+///
+/// ```
+/// use ai_dataloader::collate::{Collate, Infallible};
+/// use ai_dataloader::indexable::DataLoader;
+///
+/// /// Sum the samples of a batch.
+/// struct Sum;
+/// impl Collate<i32> for Sum {
+///     type Output = i32;
+///     fn collate(&self, batch: Vec<i32>) -> Self::Output {
+///         batch.into_iter().sum()
+///     }
+/// }
+///
+/// let dataset: Vec<Result<i32, String>> = vec![Ok(1), Ok(2), Ok(3)];
+/// let loader = DataLoader::builder(dataset)
+///     .batch_size(2)
+///     .try_collate_fn(Infallible(Sum))
+///     .build();
+/// assert_eq!(loader.try_iter().collect::<Vec<_>>(), vec![Ok(3), Ok(3)]);
+/// ```
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Infallible<C>(pub C);
+
+impl<T, C: Collate<T>> Collate<T> for Infallible<C> {
+    type Output = C::Output;
+    fn collate(&self, batch: Vec<T>) -> Self::Output {
+        self.0.collate(batch)
+    }
+}
+
+impl<T, C: Collate<T>> TryCollate<T> for Infallible<C> {
+    type Output = C::Output;
+    fn try_collate(&self, batch: Vec<T>) -> Result<Self::Output, CollateError> {
+        Ok(self.0.collate(batch))
+    }
+}
+
 /// Simple Collate that doesn't change the batch of samples.
-#[derive(Default, Debug)]
+#[derive(Default, Debug, Clone, Copy)]
 pub struct NoOpCollate;
 
 impl<T> Collate<T> for NoOpCollate {
@@ -46,6 +133,13 @@ impl<T> Collate<T> for NoOpCollate {
     }
 }
 
+impl<T> TryCollate<T> for NoOpCollate {
+    type Output = Vec<T>;
+    fn try_collate(&self, batch: Vec<T>) -> Result<Self::Output, CollateError> {
+        Ok(batch)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
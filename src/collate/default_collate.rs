@@ -11,6 +11,7 @@
 /// - `Vec<tuple>` -> `tuple(ndarray)`
 /// - `Vec<HashMap<Key, Value>>` -> `HasMap<Key, DefaultCollate::default().collate(Vec<Value>)`
 /// - `Vec<Array>` -> `Vec<Stack Array>`
+/// - `Vec<ArrayView>` -> `Stack Array`
 /// - `Vec[V1_i, V2_i, ...]` -> `Vec[DefaultCollate::default().collate([V1_1, V1_2, ...]), DefaultCollate::default().collate([V2_1, V2_2, ...]), ...]`
 ///
 ///
@@ -20,6 +21,8 @@
 /// - `Vec<&str>` -> `Vec<&str>`
 /// - `Vec<u8>` -> `Vec<u8>`
 ///
+/// The collation is implemented by [`TryCollate`], returning a [`CollateError`](super::CollateError) for samples that
+/// can't be collated together, such as arrays of different shapes. [`Collate`] panics with the message of this error.
 ///
 #[derive(Default, Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
 pub struct DefaultCollate;
@@ -33,3 +36,16 @@ mod reference;
 mod sequence;
 mod string;
 mod tuple;
+
+use super::{Collate, TryCollate};
+
+impl<T> Collate<T> for DefaultCollate
+where
+    Self: TryCollate<T>,
+{
+    type Output = <Self as TryCollate<T>>::Output;
+    fn collate(&self, batch: Vec<T>) -> Self::Output {
+        self.try_collate(batch)
+            .unwrap_or_else(|error| panic!("{error}"))
+    }
+}
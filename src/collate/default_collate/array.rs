@@ -1,25 +1,29 @@
-use super::super::Collate;
+use super::super::{CollateError, TryCollate};
 use super::DefaultCollate;
 
-impl<T, const N: usize> Collate<[T; N]> for DefaultCollate
+impl<T, const N: usize> TryCollate<[T; N]> for DefaultCollate
 where
-    Self: Collate<T>,
+    Self: TryCollate<T>,
     T: Clone,
 {
-    type Output = Vec<<Self as Collate<T>>::Output>;
-    fn collate(&self, batch: Vec<[T; N]>) -> Self::Output {
-        let mut collated = Vec::with_capacity(batch.len());
-        for i in 0..batch[0].len() {
+    type Output = Vec<<Self as TryCollate<T>>::Output>;
+    fn try_collate(&self, batch: Vec<[T; N]>) -> Result<Self::Output, CollateError> {
+        let mut collated = Vec::with_capacity(N);
+        for i in 0..N {
             let vec: Vec<_> = batch.iter().map(|sample| sample[i].clone()).collect();
-            collated.push(self.collate(vec));
+            collated.push(
+                self.try_collate(vec)
+                    .map_err(|error| error.in_field(&format!("[{i}]")))?,
+            );
         }
-        collated
+        Ok(collated)
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::collate::Collate;
     use ndarray::array;
 
     #[test]
@@ -1,48 +1,60 @@
-use super::super::Collate;
+use super::super::{map_values, CollateError, TryCollate};
 use super::DefaultCollate;
 use std::{
     cmp::Eq,
     collections::{BTreeMap, HashMap},
+    fmt::Debug,
     hash::{BuildHasher, Hash},
 };
 
-impl<K, V, H> Collate<HashMap<K, V, H>> for DefaultCollate
+impl<K, V, H> TryCollate<HashMap<K, V, H>> for DefaultCollate
 where
-    K: Eq + Hash + Clone,
+    K: Eq + Hash + Clone + Debug,
     V: Clone,
-    Self: Collate<V>,
+    Self: TryCollate<V>,
     H: BuildHasher,
 {
-    type Output = HashMap<K, <Self as Collate<V>>::Output>;
-    fn collate(&self, batch: Vec<HashMap<K, V, H>>) -> Self::Output {
-        let mut collated = HashMap::with_capacity(batch[0].keys().len());
-        for key in batch[0].keys() {
-            let vec: Vec<_> = batch.iter().map(|hash_map| hash_map[key].clone()).collect();
-            collated.insert(key.clone(), self.collate(vec));
+    type Output = HashMap<K, <Self as TryCollate<V>>::Output>;
+    fn try_collate(&self, batch: Vec<HashMap<K, V, H>>) -> Result<Self::Output, CollateError> {
+        let first = batch.first().ok_or_else(CollateError::empty_batch)?;
+        let mut collated = HashMap::with_capacity(first.len());
+        for key in first.keys() {
+            let vec = map_values(&batch, key, HashMap::get)?;
+            collated.insert(
+                key.clone(),
+                self.try_collate(vec)
+                    .map_err(|error| error.in_field(&format!("[{key:?}]")))?,
+            );
         }
-        collated
+        Ok(collated)
     }
 }
-impl<K, V> Collate<BTreeMap<K, V>> for DefaultCollate
+impl<K, V> TryCollate<BTreeMap<K, V>> for DefaultCollate
 where
-    K: Ord + Clone,
+    K: Ord + Clone + Debug,
     V: Clone,
-    Self: Collate<V>,
+    Self: TryCollate<V>,
 {
-    type Output = BTreeMap<K, <Self as Collate<V>>::Output>;
-    fn collate(&self, batch: Vec<BTreeMap<K, V>>) -> Self::Output {
+    type Output = BTreeMap<K, <Self as TryCollate<V>>::Output>;
+    fn try_collate(&self, batch: Vec<BTreeMap<K, V>>) -> Result<Self::Output, CollateError> {
+        let first = batch.first().ok_or_else(CollateError::empty_batch)?;
         let mut collated = BTreeMap::new();
-        for key in batch[0].keys() {
-            let vec: Vec<_> = batch.iter().map(|hash_map| hash_map[key].clone()).collect();
-            collated.insert(key.clone(), self.collate(vec));
+        for key in first.keys() {
+            let vec = map_values(&batch, key, BTreeMap::get)?;
+            collated.insert(
+                key.clone(),
+                self.try_collate(vec)
+                    .map_err(|error| error.in_field(&format!("[{key:?}]")))?,
+            );
         }
-        collated
+        Ok(collated)
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::collate::{Collate, CollateErrorKind};
     use ndarray::array;
 
     #[test]
@@ -79,4 +91,27 @@ mod tests {
         let expected_result = HashMap::from([("A", vec!["0", "100"]), ("B", vec!["1", "100"])]);
         assert_eq!(DefaultCollate.collate(vec![map1, map2]), expected_result);
     }
+
+    #[test]
+    fn missing_key() {
+        let map1 = HashMap::from([("A", 0), ("B", 1)]);
+        let map2 = HashMap::from([("A", 100)]);
+        let error = DefaultCollate.try_collate(vec![map1, map2]).unwrap_err();
+        assert_eq!(
+            error.kind,
+            CollateErrorKind::MissingKey {
+                key: String::from("\"B\"")
+            }
+        );
+        assert_eq!(error.batch_indices, [1]);
+
+        let map1 = BTreeMap::from([("A", (vec![0, 1], 0))]);
+        let map2 = BTreeMap::from([("A", (vec![2], 1))]);
+        let error = DefaultCollate.try_collate(vec![map1, map2]).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "failed to collate the field `[\"A\"].0` of the samples: \
+            the samples [1] have the lengths [1], different from the length 2 of the sample 0"
+        );
+    }
 }
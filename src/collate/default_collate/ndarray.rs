@@ -1,18 +1,70 @@
-use super::super::Collate;
+use super::super::{CollateError, TryCollate};
 use super::DefaultCollate;
 use ndarray::{stack, Array, ArrayBase, ArrayView, Axis, Dimension, RemoveAxis};
 
-impl<A, D> Collate<Array<A, D>> for DefaultCollate
+impl<A, D> TryCollate<Array<A, D>> for DefaultCollate
 where
     A: Clone,
     D: Dimension,
     D::Larger: RemoveAxis,
 {
     type Output = Array<A, <D as Dimension>::Larger>;
-    fn collate(&self, batch: Vec<Array<A, D>>) -> Self::Output {
+    fn try_collate(&self, batch: Vec<Array<A, D>>) -> Result<Self::Output, CollateError> {
         // Convert it to a `Vec` of view.
         let vec_of_view: Vec<ArrayView<'_, A, D>> = batch.iter().map(ArrayBase::view).collect();
-        stack(Axis(0), vec_of_view.as_slice())
-            .expect("Make sure you're items from the dataset have the same shape.")
+        self.try_collate(vec_of_view)
+    }
+}
+
+/// Views are stacked directly into the batch, so a dataset yielding views copies its data only once.
+impl<'a, A, D> TryCollate<ArrayView<'a, A, D>> for DefaultCollate
+where
+    A: Clone,
+    D: Dimension,
+    D::Larger: RemoveAxis,
+{
+    type Output = Array<A, <D as Dimension>::Larger>;
+    fn try_collate(&self, batch: Vec<ArrayView<'a, A, D>>) -> Result<Self::Output, CollateError> {
+        stack(Axis(0), batch.as_slice()).map_err(|_| {
+            CollateError::shape_mismatch(batch.iter().map(|array| array.shape().to_vec()).collect())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::collate::{Collate, CollateErrorKind};
+    use ndarray::array;
+
+    #[test]
+    fn vec_of_view() {
+        let data = array![[1, 2], [3, 4], [5, 6]];
+        assert_eq!(
+            DefaultCollate.collate(vec![data.row(2), data.row(0)]),
+            array![[5, 6], [1, 2]]
+        );
+    }
+
+    #[test]
+    fn different_shapes() {
+        let error = DefaultCollate
+            .try_collate(vec![array![1, 2], array![3, 4, 5], array![6, 7]])
+            .unwrap_err();
+        assert_eq!(
+            error.kind,
+            CollateErrorKind::ShapeMismatch {
+                shapes: vec![vec![2], vec![3], vec![2]]
+            }
+        );
+        assert_eq!(error.batch_indices, [1]);
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "failed to collate the samples: the samples [1] have the shapes [[3]], different from the shape [2] of the sample 0"
+    )]
+    fn different_shapes_panic() {
+        let _batch = DefaultCollate.collate(vec![array![1, 2], array![3, 4, 5]]);
     }
 }
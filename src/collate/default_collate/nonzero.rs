@@ -1,4 +1,4 @@
-use super::super::Collate;
+use super::super::{CollateError, TryCollate};
 use super::DefaultCollate;
 use ndarray::{Array, Ix1};
 use std::num::{
@@ -6,15 +6,15 @@ use std::num::{
     NonZeroU16, NonZeroU32, NonZeroU64, NonZeroU8, NonZeroUsize,
 };
 
-/// Maybe this one may not be supported by a tensor running on a GPU.
+// Maybe this one may not be supported by a tensor running on a GPU.
 
 macro_rules! nonzero_impl {
     ($($t:ty)*) => {
         $(
-            impl Collate<$t> for DefaultCollate {
+            impl TryCollate<$t> for DefaultCollate {
                 type Output = Array<$t, Ix1>;
-                fn collate(&self, batch: Vec<$t>) -> Self::Output {
-                    Array::from_vec(batch)
+                fn try_collate(&self, batch: Vec<$t>) -> Result<Self::Output, CollateError> {
+                    Ok(Array::from_vec(batch))
                 }
             }
         )*
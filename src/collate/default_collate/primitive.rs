@@ -1,4 +1,4 @@
-use super::super::Collate;
+use super::super::{CollateError, TryCollate};
 use super::DefaultCollate;
 
 use ndarray::{Array, Array1};
@@ -6,10 +6,10 @@ use ndarray::{Array, Array1};
 macro_rules! primitive_impl {
     ($($t:ty)*) => {
         $(
-            impl Collate<$t> for DefaultCollate {
+            impl TryCollate<$t> for DefaultCollate {
                 type Output = Array1<$t>;
-                fn collate(&self, batch: Vec<$t>) -> Self::Output {
-                    Array::from_vec(batch)
+                fn try_collate(&self, batch: Vec<$t>) -> Result<Self::Output, CollateError> {
+                    Ok(Array::from_vec(batch))
                 }
             }
         )*
@@ -21,16 +21,17 @@ primitive_impl!(usize u16 u32 u64 u128
     bool char);
 
 /// `NoOp` for binary, as pytorch `default_collate` function.
-impl Collate<u8> for DefaultCollate {
+impl TryCollate<u8> for DefaultCollate {
     type Output = Vec<u8>;
-    fn collate(&self, batch: Vec<u8>) -> Self::Output {
-        batch
+    fn try_collate(&self, batch: Vec<u8>) -> Result<Self::Output, CollateError> {
+        Ok(batch)
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::collate::Collate;
     use ndarray::array;
 
     #[test]
@@ -1,16 +1,16 @@
 use super::DefaultCollate;
-use crate::collate::Collate;
+use crate::collate::{CollateError, TryCollate};
 
 /// We think it makes no sense to but a bench of reference into a Tensor. That's why if the dataset yield reference a
 /// we clone them them.
 /// It is useful for having a non-consuming `Iterator` over the `Dataloader`.
-impl<T> Collate<&T> for DefaultCollate
+impl<T> TryCollate<&T> for DefaultCollate
 where
     T: Clone,
-    Self: Collate<T>,
+    Self: TryCollate<T>,
 {
-    type Output = <Self as Collate<T>>::Output;
-    fn collate(&self, batch: Vec<&T>) -> Self::Output {
-        DefaultCollate.collate(batch.into_iter().cloned().collect())
+    type Output = <Self as TryCollate<T>>::Output;
+    fn try_collate(&self, batch: Vec<&T>) -> Result<Self::Output, CollateError> {
+        self.try_collate(batch.into_iter().cloned().collect())
     }
 }
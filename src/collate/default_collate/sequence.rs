@@ -3,67 +3,58 @@
 /// Currently `BinaryHeap`, `BTreeSet`, `HashSet` and `LinkedList` are not supported because the current implementation
 /// require indexing for doing the transpose.
 ///
-use super::super::Collate;
+use super::super::{common_length, CollateError, TryCollate};
 use super::DefaultCollate;
 use std::collections::VecDeque;
 
-impl<T> Collate<Vec<T>> for DefaultCollate
+impl<T> TryCollate<Vec<T>> for DefaultCollate
 where
-    Self: Collate<T>,
+    Self: TryCollate<T>,
     T: Clone,
 {
-    type Output = Vec<<Self as Collate<T>>::Output>;
-    fn collate(&self, batch: Vec<Vec<T>>) -> Self::Output {
-        let elem_size = batch
-            .first()
-            .expect("Batch should contain at least one element")
-            .len();
+    type Output = Vec<<Self as TryCollate<T>>::Output>;
+    fn try_collate(&self, batch: Vec<Vec<T>>) -> Result<Self::Output, CollateError> {
+        let elem_size = common_length(batch.iter().map(Vec::len).collect())?;
 
-        assert!(
-            batch.iter().all(|vec| vec.len() == elem_size),
-            "Each Vec in the batch should have equal size"
-        );
-
-        let mut collated = Vec::with_capacity(batch.len());
+        let mut collated = Vec::with_capacity(elem_size);
 
-        for i in 0..batch[0].len() {
+        for i in 0..elem_size {
             let vec: Vec<_> = batch.iter().map(|sample| sample[i].clone()).collect();
-            collated.push(self.collate(vec));
+            collated.push(
+                self.try_collate(vec)
+                    .map_err(|error| error.in_field(&format!("[{i}]")))?,
+            );
         }
-        collated
+        Ok(collated)
     }
 }
 
-impl<T> Collate<VecDeque<T>> for DefaultCollate
+impl<T> TryCollate<VecDeque<T>> for DefaultCollate
 where
-    Self: Collate<T>,
+    Self: TryCollate<T>,
     T: Clone,
 {
-    type Output = Vec<<Self as Collate<T>>::Output>;
-    fn collate(&self, batch: Vec<VecDeque<T>>) -> Self::Output {
-        let elem_size = batch
-            .first()
-            .expect("Batch should contain at least one element")
-            .len();
-
-        assert!(
-            batch.iter().all(|vec| vec.len() == elem_size),
-            "Each Vec in the batch should have equal size"
-        );
+    type Output = Vec<<Self as TryCollate<T>>::Output>;
+    fn try_collate(&self, batch: Vec<VecDeque<T>>) -> Result<Self::Output, CollateError> {
+        let elem_size = common_length(batch.iter().map(VecDeque::len).collect())?;
 
-        let mut collated = Vec::with_capacity(batch.len());
+        let mut collated = Vec::with_capacity(elem_size);
 
-        for i in 0..batch[0].len() {
+        for i in 0..elem_size {
             let vec: Vec<_> = batch.iter().map(|sample| sample[i].clone()).collect();
-            collated.push(self.collate(vec));
+            collated.push(
+                self.try_collate(vec)
+                    .map_err(|error| error.in_field(&format!("[{i}]")))?,
+            );
         }
-        collated
+        Ok(collated)
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::collate::{Collate, CollateErrorKind};
     use ndarray::array;
 
     #[test]
@@ -122,4 +113,35 @@ mod tests {
             ]
         );
     }
+
+    #[test]
+    fn different_lengths() {
+        let error = DefaultCollate
+            .try_collate(vec![vec![1, 2], vec![3, 4], vec![5]])
+            .unwrap_err();
+        assert_eq!(
+            error.kind,
+            CollateErrorKind::LengthMismatch {
+                lengths: vec![2, 2, 1]
+            }
+        );
+        assert_eq!(error.batch_indices, [2]);
+
+        let error = DefaultCollate
+            .try_collate(vec![
+                vec![ndarray::array![1, 2], ndarray::array![3]],
+                vec![ndarray::array![4, 5], ndarray::array![6, 7]],
+            ])
+            .unwrap_err();
+        assert_eq!(error.path, "[1]");
+        assert_eq!(error.batch_indices, [1]);
+    }
+
+    #[test]
+    fn empty_batch() {
+        let error = DefaultCollate
+            .try_collate(Vec::<Vec<i32>>::new())
+            .unwrap_err();
+        assert_eq!(error.kind, CollateErrorKind::EmptyBatch);
+    }
 }
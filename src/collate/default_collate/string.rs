@@ -1,45 +1,46 @@
-use super::super::Collate;
+use super::super::{CollateError, TryCollate};
 use super::DefaultCollate;
 use std::ffi::{CStr, CString, OsString};
 
-impl Collate<String> for DefaultCollate {
+impl TryCollate<String> for DefaultCollate {
     type Output = Vec<String>;
-    fn collate(&self, batch: Vec<String>) -> Self::Output {
-        batch
+    fn try_collate(&self, batch: Vec<String>) -> Result<Self::Output, CollateError> {
+        Ok(batch)
     }
 }
 
-impl<'a> Collate<&'a str> for DefaultCollate {
+impl<'a> TryCollate<&'a str> for DefaultCollate {
     type Output = Vec<&'a str>;
-    fn collate(&self, batch: Vec<&'a str>) -> Self::Output {
-        batch
+    fn try_collate(&self, batch: Vec<&'a str>) -> Result<Self::Output, CollateError> {
+        Ok(batch)
     }
 }
 
-impl Collate<CString> for DefaultCollate {
+impl TryCollate<CString> for DefaultCollate {
     type Output = Vec<CString>;
-    fn collate(&self, batch: Vec<CString>) -> Self::Output {
-        batch
+    fn try_collate(&self, batch: Vec<CString>) -> Result<Self::Output, CollateError> {
+        Ok(batch)
     }
 }
 
-impl<'a> Collate<&'a CStr> for DefaultCollate {
+impl<'a> TryCollate<&'a CStr> for DefaultCollate {
     type Output = Vec<&'a CStr>;
-    fn collate(&self, batch: Vec<&'a CStr>) -> Self::Output {
-        batch
+    fn try_collate(&self, batch: Vec<&'a CStr>) -> Result<Self::Output, CollateError> {
+        Ok(batch)
     }
 }
 
-impl Collate<OsString> for DefaultCollate {
+impl TryCollate<OsString> for DefaultCollate {
     type Output = Vec<OsString>;
-    fn collate(&self, batch: Vec<OsString>) -> Self::Output {
-        batch
+    fn try_collate(&self, batch: Vec<OsString>) -> Result<Self::Output, CollateError> {
+        Ok(batch)
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::collate::Collate;
 
     #[test]
     fn no_op() {
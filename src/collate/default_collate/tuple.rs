@@ -1,4 +1,4 @@
-use super::super::Collate;
+use super::super::{CollateError, TryCollate};
 use super::DefaultCollate;
 use itertools::Itertools;
 
@@ -7,44 +7,45 @@ use itertools::Itertools;
 
 /// `tuple` implementation, up to 16 elements.
 macro_rules! tuple_impl {
-    ($($name:ident)+) => {
-        impl<$($name),+> Collate<($($name,)+)> for DefaultCollate
+    ($($name:ident $idx:tt)+) => {
+        impl<$($name),+> TryCollate<($($name,)+)> for DefaultCollate
         where
             $($name: Clone,)+
-            $(DefaultCollate: Collate<$name>,)+
+            $(DefaultCollate: TryCollate<$name>,)+
 
         {
-            type Output = ($(<DefaultCollate as Collate<$name>>::Output,)+);
+            type Output = ($(<DefaultCollate as TryCollate<$name>>::Output,)+);
 
             #[allow(non_snake_case)]
-            fn collate(&self, batch: Vec<($($name,)+)>) -> Self::Output {
+            fn try_collate(&self, batch: Vec<($($name,)+)>) -> Result<Self::Output, CollateError> {
                 let copy = batch.to_vec();
                 let ($($name,)+) = copy.into_iter().multiunzip();
-                (
-                    $(DefaultCollate::default().collate($name),)+
-                )
+                Ok((
+                    $(self.try_collate($name).map_err(|error| error.in_field(concat!(".", stringify!($idx))))?,)+
+                ))
 
             }
         }
     };
 }
 
-tuple_impl! { A }
-tuple_impl! { A B }
-tuple_impl! { A B C }
-tuple_impl! { A B C D }
-tuple_impl! { A B C D E }
-tuple_impl! { A B C D E F }
-tuple_impl! { A B C D E F G }
-tuple_impl! { A B C D E F G H }
-tuple_impl! { A B C D E F G H I }
-tuple_impl! { A B C D E F G H I J }
-tuple_impl! { A B C D E F G H I J K }
-tuple_impl! { A B C D E F G H I J K L }
+tuple_impl! { A 0 }
+tuple_impl! { A 0 B 1 }
+tuple_impl! { A 0 B 1 C 2 }
+tuple_impl! { A 0 B 1 C 2 D 3 }
+tuple_impl! { A 0 B 1 C 2 D 3 E 4 }
+tuple_impl! { A 0 B 1 C 2 D 3 E 4 F 5 }
+tuple_impl! { A 0 B 1 C 2 D 3 E 4 F 5 G 6 }
+tuple_impl! { A 0 B 1 C 2 D 3 E 4 F 5 G 6 H 7 }
+tuple_impl! { A 0 B 1 C 2 D 3 E 4 F 5 G 6 H 7 I 8 }
+tuple_impl! { A 0 B 1 C 2 D 3 E 4 F 5 G 6 H 7 I 8 J 9 }
+tuple_impl! { A 0 B 1 C 2 D 3 E 4 F 5 G 6 H 7 I 8 J 9 K 10 }
+tuple_impl! { A 0 B 1 C 2 D 3 E 4 F 5 G 6 H 7 I 8 J 9 K 10 L 11 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::collate::Collate;
     use ndarray::array;
 
     #[test]
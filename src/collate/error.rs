@@ -0,0 +1,192 @@
+use std::error::Error;
+use std::fmt::{self, Debug};
+
+/// Error of a batch that can't be collated, returned by [`TryCollate`](super::TryCollate).
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct CollateError {
+    /// Path of the field that can't be collated inside the samples, for instance `.0["image"][2]`
+    /// for the third element of the `"image"` entry of the first element of a tuple.
+    /// It's empty if the samples themselves can't be collated.
+    pub path: String,
+    /// What went wrong.
+    pub kind: CollateErrorKind,
+    /// Positions in the batch of the samples at fault, the ones that differ from the first sample.
+    pub batch_indices: Vec<usize>,
+}
+
+/// The reason why a batch can't be collated.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum CollateErrorKind {
+    /// The batch doesn't contain any sample, so there is nothing to take the structure of the batch from.
+    EmptyBatch,
+    /// The arrays don't have the same shape, so they can't be stacked.
+    ShapeMismatch {
+        /// The shape of each sample.
+        shapes: Vec<Vec<usize>>,
+    },
+    /// The sequences don't have the same length, so they can't be transposed.
+    LengthMismatch {
+        /// The length of each sample.
+        lengths: Vec<usize>,
+    },
+    /// Some maps don't contain a key of the first sample.
+    MissingKey {
+        /// The `Debug` representation of the key.
+        key: String,
+    },
+}
+
+impl CollateError {
+    /// Error of a batch without any sample.
+    pub(crate) fn empty_batch() -> Self {
+        Self {
+            path: String::new(),
+            kind: CollateErrorKind::EmptyBatch,
+            batch_indices: Vec::new(),
+        }
+    }
+
+    /// Error of arrays that can't be stacked, given the shape of each of them.
+    pub(crate) fn shape_mismatch(shapes: Vec<Vec<usize>>) -> Self {
+        if shapes.is_empty() {
+            return Self::empty_batch();
+        }
+        Self {
+            path: String::new(),
+            batch_indices: differing(&shapes),
+            kind: CollateErrorKind::ShapeMismatch { shapes },
+        }
+    }
+
+    /// Error of maps missing the `key`, at the positions `batch_indices` in the batch.
+    pub(crate) fn missing_key(key: String, batch_indices: Vec<usize>) -> Self {
+        Self {
+            path: String::new(),
+            kind: CollateErrorKind::MissingKey { key },
+            batch_indices,
+        }
+    }
+
+    /// Prepend the `field` that contained the values that couldn't be collated to the path of the error.
+    #[must_use]
+    pub(crate) fn in_field(mut self, field: &str) -> Self {
+        self.path.insert_str(0, field);
+        self
+    }
+}
+
+/// Return the common length of the sequences of a batch, or the error if the batch is empty or if they differ.
+pub(crate) fn common_length(lengths: Vec<usize>) -> Result<usize, CollateError> {
+    match lengths.first() {
+        None => Err(CollateError::empty_batch()),
+        Some(len) if lengths.iter().all(|other| other == len) => Ok(*len),
+        Some(_) => Err(CollateError {
+            path: String::new(),
+            batch_indices: differing(&lengths),
+            kind: CollateErrorKind::LengthMismatch { lengths },
+        }),
+    }
+}
+
+/// Gather the values of `key` of each map of the batch, or the error of the maps missing it.
+///
+/// `get` looks the key up in a map, it's shared by the collate functions of every map type.
+pub(crate) fn map_values<'a, M, K: Debug, V: Clone + 'a>(
+    batch: &'a [M],
+    key: &K,
+    get: impl Fn(&'a M, &K) -> Option<&'a V>,
+) -> Result<Vec<V>, CollateError> {
+    let values: Vec<_> = batch.iter().map(|map| get(map, key)).collect();
+    if values.iter().all(Option::is_some) {
+        return Ok(values.into_iter().flatten().cloned().collect());
+    }
+    let missing = values
+        .iter()
+        .enumerate()
+        .filter(|(_, value)| value.is_none())
+        .map(|(position, _)| position)
+        .collect();
+    Err(CollateError::missing_key(format!("{key:?}"), missing))
+}
+
+/// Positions of the values different from the first one.
+fn differing<T: PartialEq>(values: &[T]) -> Vec<usize> {
+    values
+        .iter()
+        .enumerate()
+        .filter(|(_, value)| **value != values[0])
+        .map(|(position, _)| position)
+        .collect()
+}
+
+impl fmt::Display for CollateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.path.is_empty() {
+            write!(f, "failed to collate the samples: ")?;
+        } else {
+            write!(
+                f,
+                "failed to collate the field `{}` of the samples: ",
+                self.path
+            )?;
+        }
+        match &self.kind {
+            CollateErrorKind::EmptyBatch => write!(f, "the batch is empty"),
+            CollateErrorKind::ShapeMismatch { shapes } => write!(
+                f,
+                "the samples {:?} have the shapes {:?}, different from the shape {:?} of the sample 0",
+                self.batch_indices,
+                self.batch_indices
+                    .iter()
+                    .map(|position| &shapes[*position])
+                    .collect::<Vec<_>>(),
+                shapes[0]
+            ),
+            CollateErrorKind::LengthMismatch { lengths } => write!(
+                f,
+                "the samples {:?} have the lengths {:?}, different from the length {} of the sample 0",
+                self.batch_indices,
+                self.batch_indices
+                    .iter()
+                    .map(|position| lengths[*position])
+                    .collect::<Vec<_>>(),
+                lengths[0]
+            ),
+            CollateErrorKind::MissingKey { key } => write!(
+                f,
+                "the samples {:?} miss the key {key} of the sample 0",
+                self.batch_indices
+            ),
+        }
+    }
+}
+
+impl Error for CollateError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn display() {
+        let error = CollateError::shape_mismatch(vec![vec![2, 3], vec![2, 3], vec![3, 3]])
+            .in_field("[1]")
+            .in_field(".0");
+        assert_eq!(error.path, ".0[1]");
+        assert_eq!(error.batch_indices, [2]);
+        assert_eq!(
+            error.to_string(),
+            "failed to collate the field `.0[1]` of the samples: the samples [2] have the shapes [[3, 3]], \
+            different from the shape [2, 3] of the sample 0"
+        );
+        assert_eq!(
+            common_length(vec![2, 1, 2, 3]).unwrap_err().to_string(),
+            "failed to collate the samples: the samples [1, 3] have the lengths [1, 3], \
+            different from the length 2 of the sample 0"
+        );
+        assert_eq!(
+            common_length(Vec::new()).unwrap_err().to_string(),
+            "failed to collate the samples: the batch is empty"
+        );
+    }
+}
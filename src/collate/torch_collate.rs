@@ -18,6 +18,8 @@
 /// - `Vec<&str>` -> `Vec<&str>`
 /// - `Vec<u8>` -> `Vec<u8>`
 ///
+/// Like [`DefaultCollate`](super::DefaultCollate), the collation is implemented by [`TryCollate`], and [`Collate`]
+/// panics with the message of the [`CollateError`](super::CollateError).
 ///
 #[derive(Default, Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
 pub struct TorchCollate;
@@ -31,3 +33,16 @@ mod reference;
 mod sequence;
 mod string;
 mod tuple;
+
+use super::{Collate, TryCollate};
+
+impl<T> Collate<T> for TorchCollate
+where
+    Self: TryCollate<T>,
+{
+    type Output = <Self as TryCollate<T>>::Output;
+    fn collate(&self, batch: Vec<T>) -> Self::Output {
+        self.try_collate(batch)
+            .unwrap_or_else(|error| panic!("{error}"))
+    }
+}
@@ -1,48 +1,60 @@
-use super::super::Collate;
+use super::super::{map_values, CollateError, TryCollate};
 use super::TorchCollate;
 use std::{
     cmp::Eq,
     collections::{BTreeMap, HashMap},
+    fmt::Debug,
     hash::{BuildHasher, Hash},
 };
 
-impl<K, V, H> Collate<HashMap<K, V, H>> for TorchCollate
+impl<K, V, H> TryCollate<HashMap<K, V, H>> for TorchCollate
 where
-    K: Eq + Hash + Clone,
+    K: Eq + Hash + Clone + Debug,
     V: Clone,
-    Self: Collate<V>,
+    Self: TryCollate<V>,
     H: BuildHasher,
 {
-    type Output = HashMap<K, <Self as Collate<V>>::Output>;
-    fn collate(&self, batch: Vec<HashMap<K, V, H>>) -> Self::Output {
-        let mut collated = HashMap::with_capacity(batch[0].keys().len());
-        for key in batch[0].keys() {
-            let vec: Vec<_> = batch.iter().map(|hash_map| hash_map[key].clone()).collect();
-            collated.insert(key.clone(), self.collate(vec));
+    type Output = HashMap<K, <Self as TryCollate<V>>::Output>;
+    fn try_collate(&self, batch: Vec<HashMap<K, V, H>>) -> Result<Self::Output, CollateError> {
+        let first = batch.first().ok_or_else(CollateError::empty_batch)?;
+        let mut collated = HashMap::with_capacity(first.len());
+        for key in first.keys() {
+            let vec = map_values(&batch, key, HashMap::get)?;
+            collated.insert(
+                key.clone(),
+                self.try_collate(vec)
+                    .map_err(|error| error.in_field(&format!("[{key:?}]")))?,
+            );
         }
-        collated
+        Ok(collated)
     }
 }
-impl<K, V> Collate<BTreeMap<K, V>> for TorchCollate
+impl<K, V> TryCollate<BTreeMap<K, V>> for TorchCollate
 where
-    K: Ord + Clone,
+    K: Ord + Clone + Debug,
     V: Clone,
-    Self: Collate<V>,
+    Self: TryCollate<V>,
 {
-    type Output = BTreeMap<K, <Self as Collate<V>>::Output>;
-    fn collate(&self, batch: Vec<BTreeMap<K, V>>) -> Self::Output {
+    type Output = BTreeMap<K, <Self as TryCollate<V>>::Output>;
+    fn try_collate(&self, batch: Vec<BTreeMap<K, V>>) -> Result<Self::Output, CollateError> {
+        let first = batch.first().ok_or_else(CollateError::empty_batch)?;
         let mut collated = BTreeMap::new();
-        for key in batch[0].keys() {
-            let vec: Vec<_> = batch.iter().map(|hash_map| hash_map[key].clone()).collect();
-            collated.insert(key.clone(), self.collate(vec));
+        for key in first.keys() {
+            let vec = map_values(&batch, key, BTreeMap::get)?;
+            collated.insert(
+                key.clone(),
+                self.try_collate(vec)
+                    .map_err(|error| error.in_field(&format!("[{key:?}]")))?,
+            );
         }
-        collated
+        Ok(collated)
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::collate::Collate;
     use tch::Tensor;
 
     #[test]
@@ -1,36 +1,44 @@
-use super::super::Collate;
+use super::super::{CollateError, TryCollate};
 use super::TorchCollate;
 use ndarray::{stack, Array, ArrayBase, ArrayView, Axis, Dimension, RemoveAxis};
 use tch::Tensor;
 
-impl<A, D> Collate<Array<A, D>> for TorchCollate
+impl<A, D> TryCollate<Array<A, D>> for TorchCollate
 where
     A: Clone + tch::kind::Element,
     D: Dimension,
     D::Larger: RemoveAxis,
 {
     type Output = Tensor;
-    fn collate(&self, batch: Vec<Array<A, D>>) -> Self::Output {
+    fn try_collate(&self, batch: Vec<Array<A, D>>) -> Result<Self::Output, CollateError> {
         // Convert it to a `Vec` of view.
         let vec_of_view: Vec<ArrayView<'_, A, D>> = batch.iter().map(ArrayBase::view).collect();
         // TODO: maybe use tensor stack here
-        let array = stack(Axis(0), vec_of_view.as_slice())
-            .expect("Make sure you're items from the dataset have the same shape.");
+        let array = stack(Axis(0), vec_of_view.as_slice()).map_err(|_| {
+            CollateError::shape_mismatch(batch.iter().map(|array| array.shape().to_vec()).collect())
+        })?;
 
-        let tensor = Tensor::from_slice(array.as_slice().unwrap());
+        // The data is copied only if the stacked array isn't contiguous.
+        let array = array.as_standard_layout();
+        let tensor = Tensor::from_slice(
+            array
+                .as_slice()
+                .expect("an array in standard layout is contiguous"),
+        );
         #[allow(clippy::cast_possible_wrap)]
         let shape = array
             .shape()
             .iter()
             .map(|dim| *dim as i64)
             .collect::<Vec<_>>();
-        tensor.reshape(shape)
+        Ok(tensor.reshape(shape))
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::collate::Collate;
     use ndarray::array;
 
     #[test]
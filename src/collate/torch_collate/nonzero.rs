@@ -1,4 +1,4 @@
-use super::super::Collate;
+use super::super::{CollateError, TryCollate};
 use super::TorchCollate;
 use ndarray::{Array, Ix1};
 use std::num::{
@@ -11,10 +11,10 @@ use std::num::{
 macro_rules! nonzero_impl {
     ($($t:ty)*) => {
         $(
-            impl Collate<$t> for TorchCollate {
+            impl TryCollate<$t> for TorchCollate {
                 type Output = Array<$t, Ix1>;
-                fn collate(&self, batch: Vec<$t>) -> Self::Output {
-                    Array::from_vec(batch)
+                fn try_collate(&self, batch: Vec<$t>) -> Result<Self::Output, CollateError> {
+                    Ok(Array::from_vec(batch))
                 }
             }
         )*
@@ -1,14 +1,14 @@
-use super::super::Collate;
+use super::super::{CollateError, TryCollate};
 use super::TorchCollate;
 use tch::Tensor;
 
 macro_rules! primitive_impl {
     ($($t:ty)*) => {
         $(
-            impl Collate<$t> for TorchCollate {
+            impl TryCollate<$t> for TorchCollate {
                 type Output = Tensor;
-                fn collate(&self, batch: Vec<$t>) -> Self::Output {
-                    Tensor::from_slice(batch.as_slice())
+                fn try_collate(&self, batch: Vec<$t>) -> Result<Self::Output, CollateError> {
+                    Ok(Tensor::from_slice(batch.as_slice()))
                 }
             }
         )*
@@ -22,16 +22,17 @@ primitive_impl!(
 // char i128 isize usize u16 u32 u64 u128 are not compatible with `tch::Tensor`.
 
 /// `NoOp` for binary, as pytorch `default_collate` function.
-impl Collate<u8> for TorchCollate {
+impl TryCollate<u8> for TorchCollate {
     type Output = Vec<u8>;
-    fn collate(&self, batch: Vec<u8>) -> Self::Output {
-        batch
+    fn try_collate(&self, batch: Vec<u8>) -> Result<Self::Output, CollateError> {
+        Ok(batch)
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::collate::Collate;
 
     #[test]
     fn scalar_type() {
@@ -3,67 +3,58 @@
 /// Currently `BinaryHeap`, `BTreeSet`, `HashSet` and `LinkedList` are not supported because the current implementation
 /// require indexing for doing the transpose.
 ///
-use super::super::Collate;
+use super::super::{common_length, CollateError, TryCollate};
 use super::TorchCollate;
 use std::collections::VecDeque;
 
-impl<T> Collate<Vec<T>> for TorchCollate
+impl<T> TryCollate<Vec<T>> for TorchCollate
 where
-    Self: Collate<T>,
+    Self: TryCollate<T>,
     T: Clone,
 {
-    type Output = Vec<<Self as Collate<T>>::Output>;
-    fn collate(&self, batch: Vec<Vec<T>>) -> Self::Output {
-        let elem_size = batch
-            .first()
-            .expect("Batch should contain at least one element")
-            .len();
+    type Output = Vec<<Self as TryCollate<T>>::Output>;
+    fn try_collate(&self, batch: Vec<Vec<T>>) -> Result<Self::Output, CollateError> {
+        let elem_size = common_length(batch.iter().map(Vec::len).collect())?;
 
-        assert!(
-            batch.iter().all(|vec| vec.len() == elem_size),
-            "Each Vec in the batch should have equal size"
-        );
-
-        let mut collated = Vec::with_capacity(batch.len());
+        let mut collated = Vec::with_capacity(elem_size);
 
-        for i in 0..batch[0].len() {
+        for i in 0..elem_size {
             let vec: Vec<_> = batch.iter().map(|sample| sample[i].clone()).collect();
-            collated.push(self.collate(vec));
+            collated.push(
+                self.try_collate(vec)
+                    .map_err(|error| error.in_field(&format!("[{i}]")))?,
+            );
         }
-        collated
+        Ok(collated)
     }
 }
 
-impl<T> Collate<VecDeque<T>> for TorchCollate
+impl<T> TryCollate<VecDeque<T>> for TorchCollate
 where
-    Self: Collate<T>,
+    Self: TryCollate<T>,
     T: Clone,
 {
-    type Output = Vec<<Self as Collate<T>>::Output>;
-    fn collate(&self, batch: Vec<VecDeque<T>>) -> Self::Output {
-        let elem_size = batch
-            .first()
-            .expect("Batch should contain at least one element")
-            .len();
-
-        assert!(
-            batch.iter().all(|vec| vec.len() == elem_size),
-            "Each Vec in the batch should have equal size"
-        );
+    type Output = Vec<<Self as TryCollate<T>>::Output>;
+    fn try_collate(&self, batch: Vec<VecDeque<T>>) -> Result<Self::Output, CollateError> {
+        let elem_size = common_length(batch.iter().map(VecDeque::len).collect())?;
 
-        let mut collated = Vec::with_capacity(batch.len());
+        let mut collated = Vec::with_capacity(elem_size);
 
-        for i in 0..batch[0].len() {
+        for i in 0..elem_size {
             let vec: Vec<_> = batch.iter().map(|sample| sample[i].clone()).collect();
-            collated.push(self.collate(vec));
+            collated.push(
+                self.try_collate(vec)
+                    .map_err(|error| error.in_field(&format!("[{i}]")))?,
+            );
         }
-        collated
+        Ok(collated)
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::collate::Collate;
 
     use tch::Tensor;
 
@@ -1,45 +1,46 @@
-use super::super::Collate;
+use super::super::{CollateError, TryCollate};
 use super::TorchCollate;
 use std::ffi::{CStr, CString, OsString};
 
-impl Collate<String> for TorchCollate {
+impl TryCollate<String> for TorchCollate {
     type Output = Vec<String>;
-    fn collate(&self, batch: Vec<String>) -> Self::Output {
-        batch
+    fn try_collate(&self, batch: Vec<String>) -> Result<Self::Output, CollateError> {
+        Ok(batch)
     }
 }
 
-impl<'a> Collate<&'a str> for TorchCollate {
+impl<'a> TryCollate<&'a str> for TorchCollate {
     type Output = Vec<&'a str>;
-    fn collate(&self, batch: Vec<&'a str>) -> Self::Output {
-        batch
+    fn try_collate(&self, batch: Vec<&'a str>) -> Result<Self::Output, CollateError> {
+        Ok(batch)
     }
 }
 
-impl Collate<CString> for TorchCollate {
+impl TryCollate<CString> for TorchCollate {
     type Output = Vec<CString>;
-    fn collate(&self, batch: Vec<CString>) -> Self::Output {
-        batch
+    fn try_collate(&self, batch: Vec<CString>) -> Result<Self::Output, CollateError> {
+        Ok(batch)
     }
 }
 
-impl<'a> Collate<&'a CStr> for TorchCollate {
+impl<'a> TryCollate<&'a CStr> for TorchCollate {
     type Output = Vec<&'a CStr>;
-    fn collate(&self, batch: Vec<&'a CStr>) -> Self::Output {
-        batch
+    fn try_collate(&self, batch: Vec<&'a CStr>) -> Result<Self::Output, CollateError> {
+        Ok(batch)
     }
 }
 
-impl Collate<OsString> for TorchCollate {
+impl TryCollate<OsString> for TorchCollate {
     type Output = Vec<OsString>;
-    fn collate(&self, batch: Vec<OsString>) -> Self::Output {
-        batch
+    fn try_collate(&self, batch: Vec<OsString>) -> Result<Self::Output, CollateError> {
+        Ok(batch)
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::collate::Collate;
 
     #[test]
     fn no_op() {
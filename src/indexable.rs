@@ -5,5 +5,11 @@ mod dataset;
 mod fetch;
 pub mod sampler;
 
-pub use dataloader::DataLoader;
-pub use dataset::{Dataset, GetSample, Len, NdarrayDataset};
+pub use dataloader::{
+    DataLoader, DataLoaderState, ErrorPolicy, LoaderError, MultiWorkerDataLoaderIter,
+    SingleProcessDataLoaderIter, SingleProcessDataLoaderRefIter, TryDataLoaderIter,
+};
+pub use dataset::{
+    ConcatDataset, Dataset, DatasetExt, GetSample, GetSampleRef, Len, Map, NdarrayDataset,
+    Ndarrays, Subset, TryGetSample,
+};
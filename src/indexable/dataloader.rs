@@ -1,14 +1,26 @@
 //! Data loader. Combines a dataset and a sampler, and provides an iterable over the given dataset.
 
-use super::fetch::{Fetcher, MapDatasetFetcher};
+use super::fetch::{Fetcher, MapDatasetFetcher, MapDatasetRefFetcher};
+#[cfg(feature = "rayon")]
+use crate::SharedThreadPool;
 use crate::{
     collate::{Collate, DefaultCollate},
-    sampler::{BatchIterator, BatchSampler, Sampler, SequentialSampler},
-    Dataset, Len,
+    rng_from_seed,
+    sampler::{BatchSampler, BatchSampling, SequentialSampler},
+    Dataset, GetSampleRef, Len,
 };
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::hash::{Hash, Hasher};
+use std::sync::{Arc, Mutex, PoisonError};
 
 mod builder;
 use builder::Builder;
+mod multi_worker;
+pub use multi_worker::MultiWorkerDataLoaderIter;
+mod try_iter;
+pub use try_iter::{ErrorPolicy, LoaderError, TryDataLoaderIter};
 
 /// Data loader. Combines a dataset and a sampler, and provides an iterable over the given dataset.
 ///
@@ -25,44 +37,263 @@ use builder::Builder;
 /// ```
 ///
 #[derive(Debug, Clone, PartialEq, PartialOrd, Hash, Eq, Ord)]
-pub struct DataLoader<D, S = SequentialSampler, C = DefaultCollate> {
-    /// Dataset from which to load the data.
-    dataset: D,
+pub struct DataLoader<D, B = BatchSampler, C = DefaultCollate> {
+    /// Dataset from which to load the data, shared with the workers of [`DataLoader::iter_prefetch`].
+    dataset: Arc<D>,
     /// Return a batch of indices at a time.
-    batch_sampler: BatchSampler<S>,
-    /// Collate function.
-    collate_fn: C,
+    batch_sampler: B,
+    /// Collate function, shared with the workers of [`DataLoader::iter_prefetch`].
+    collate_fn: Arc<C>,
+    /// Seed of the random number generator given to the sampler.
+    seed: Option<u64>,
+    /// Number of background threads of [`DataLoader::iter_prefetch`].
+    num_workers: usize,
+    /// Number of batches fetched in advance by each worker of [`DataLoader::iter_prefetch`].
+    prefetch_factor: usize,
+    /// Whether [`DataLoader::iter_prefetch`] yields the batches in the order of the batch sampler.
+    in_order: bool,
+    /// How [`DataLoader::try_iter`] handles the samples that can't be loaded.
+    error_policy: ErrorPolicy,
+    /// Thread pool fetching the samples of a batch in parallel.
+    #[cfg(feature = "rayon")]
+    thread_pool: SharedThreadPool,
+    /// Current epoch, mixed with the seed to draw a different permutation at each epoch.
+    epoch: u64,
+    /// State loaded with [`DataLoader::load_state_dict`], the next iteration of its epoch resumes from it.
+    resume_state: ResumeState,
 }
 
-impl<D> DataLoader<D, SequentialSampler, DefaultCollate>
+/// State loaded with [`DataLoader::load_state_dict`], taken by the first iteration that resumes from it.
+///
+/// The iterations only borrow the loader, so the state is behind a mutex. It's compared and hashed by value,
+/// so that the loaders can still be compared and hashed.
+#[derive(Debug, Default)]
+struct ResumeState(Mutex<Option<DataLoaderState>>);
+
+impl ResumeState {
+    /// The state, if it hasn't been taken yet.
+    fn get(&self) -> Option<DataLoaderState> {
+        *self.0.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Replace the state.
+    fn set(&mut self, state: Option<DataLoaderState>) {
+        *self.0.get_mut().unwrap_or_else(PoisonError::into_inner) = state;
+    }
+
+    /// Take the state, so that only one iteration resumes from it.
+    fn take(&self) -> Option<DataLoaderState> {
+        self.0.lock().unwrap_or_else(PoisonError::into_inner).take()
+    }
+}
+
+impl Clone for ResumeState {
+    fn clone(&self) -> Self {
+        Self(Mutex::new(self.get()))
+    }
+}
+
+impl PartialEq for ResumeState {
+    fn eq(&self, other: &Self) -> bool {
+        self.get() == other.get()
+    }
+}
+
+impl Eq for ResumeState {}
+
+impl PartialOrd for ResumeState {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for ResumeState {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.get().cmp(&other.get())
+    }
+}
+
+impl Hash for ResumeState {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.get().hash(state);
+    }
+}
+
+/// Progress of an iteration over a [`DataLoader`], to checkpoint it and resume it after an interruption.
+///
+/// It is returned by [`SingleProcessDataLoaderIter::state_dict`] and restored with [`DataLoader::load_state_dict`].
+/// The iteration must be resumed with the same dataset and batch sampler: only the seed of the permutation is stored,
+/// not the permutation itself.
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd, Hash, Eq, Ord, Serialize, Deserialize)]
+pub struct DataLoaderState {
+    /// The epoch of the iteration.
+    pub epoch: u64,
+    /// The seed of the random number generator given to the batch sampler.
+    ///
+    /// If the loader has no seed, a seed is drawn from the system entropy at the beginning of each iteration.
+    pub seed: u64,
+    /// Number of batches of the batch sampler already yielded, the resumed iteration starts after them.
+    pub num_yielded: u64,
+}
+
+impl<D> DataLoader<D, BatchSampler<SequentialSampler>, DefaultCollate>
 where
     D: Dataset,
-    DefaultCollate: Collate<D::Sample>,
 {
     /// Helper to return a [`DataLoader`] builder.
-    pub fn builder(dataset: D) -> Builder<D, SequentialSampler, DefaultCollate> {
+    pub fn builder(dataset: D) -> Builder<D, BatchSampler<SequentialSampler>, DefaultCollate> {
         Builder::new(dataset)
     }
 }
 
-impl<D, S, C> DataLoader<D, S, C>
+impl<D, B, C> DataLoader<D, B, C>
+where
+    D: Dataset,
+    B: BatchSampling,
+{
+    /// Set the current epoch, to call at the beginning of each epoch.
+    ///
+    /// With a seed, each epoch gets its own reproducible permutation. The epoch is also given to the sampler,
+    /// for samplers whose order depends on it such as the [`DistributedSampler`](crate::sampler::DistributedSampler),
+    /// and to the dataset, for its random augmentations.
+    ///
+    /// ```
+    /// use ai_dataloader::indexable::DataLoader;
+    ///
+    /// let mut loader = DataLoader::builder(vec![1, 2, 3, 4]).shuffle().seed(42).build();
+    /// for epoch in 0..3 {
+    ///     loader.set_epoch(epoch);
+    ///     for batch in &loader {
+    ///         println!("epoch {epoch}: {batch:?}");
+    ///     }
+    /// }
+    /// ```
+    pub fn set_epoch(&mut self, epoch: u64) {
+        if epoch != self.epoch {
+            self.resume_state.set(None);
+        }
+        self.epoch = epoch;
+        self.batch_sampler.set_epoch(epoch);
+        self.dataset.set_epoch(epoch);
+    }
+
+    /// The current epoch.
+    pub fn epoch(&self) -> u64 {
+        self.epoch
+    }
+
+    /// Restore a checkpointed iteration: set the epoch of `state`, and make the next iteration start at the first
+    /// batch not yet yielded.
+    ///
+    /// Only the next iteration resumes, the following ones start from the beginning, as do the iterations
+    /// once another epoch is set with [`DataLoader::set_epoch`].
+    ///
+    /// ```
+    /// use ai_dataloader::indexable::DataLoader;
+    ///
+    /// let mut loader = DataLoader::builder(vec![1, 2, 3, 4, 5, 6]).batch_size(2).shuffle().build();
+    /// let mut iter = loader.iter();
+    /// iter.next();
+    /// // Checkpoint after the first batch, the state can be saved with any serde format.
+    /// let state = iter.state_dict();
+    /// let second = iter.next();
+    ///
+    /// loader.load_state_dict(state);
+    /// let mut resumed = loader.iter();
+    /// assert_eq!(resumed.len(), 2);
+    /// assert_eq!(resumed.next(), second);
+    /// ```
+    pub fn load_state_dict(&mut self, state: DataLoaderState) {
+        self.set_epoch(state.epoch);
+        self.resume_state.set(Some(state));
+    }
+}
+
+impl<D, B, C> DataLoader<D, B, C>
 where
     D: Dataset + Sync,
-    S: Sampler,
+    B: BatchSampling,
     C: Collate<D::Sample>,
     D::Sample: Send,
 {
     /// Return not owning iterator over the dataloader.
-    pub fn iter(&self) -> SingleProcessDataLoaderIter<'_, D, S, C> {
+    ///
+    /// The iterator panics if the samples can't be collated, use [`DataLoader::try_iter`] to get the error instead.
+    pub fn iter(&self) -> SingleProcessDataLoaderIter<'_, D, B, C> {
         SingleProcessDataLoaderIter::new(self)
     }
 }
 
-impl<D, S, C> Len for DataLoader<D, S, C>
+impl<D, B, C> DataLoader<D, B, C>
 where
-    D: Dataset,
-    S: Sampler,
-    C: Collate<D::Sample>,
+    D: GetSampleRef + Sync,
+    B: BatchSampling,
+{
+    /// Return a not owning iterator over the dataloader whose collate function gets samples borrowed from the dataset,
+    /// with [`GetSampleRef`], instead of owned ones.
+    ///
+    /// It avoids to copy each sample before the collate function copies it again into the batch. For instance
+    /// the [`DefaultCollate`] stacks the views of a [`NdarrayDataset`](crate::indexable::NdarrayDataset) straight
+    /// into the batch. The order of the batches, the epoch, the checkpoints and the panic on the samples that can't be
+    /// collated are the same as with [`DataLoader::iter`].
+    ///
+    /// ```
+    /// use ai_dataloader::indexable::{DataLoader, NdarrayDataset};
+    /// use ndarray::array;
+    ///
+    /// let dataset = NdarrayDataset::new((array![[1, 2], [3, 4], [5, 6]], array![0, 1, 0]));
+    /// let loader = DataLoader::builder(dataset).batch_size(2).build();
+    /// let mut iter = loader.iter_ref();
+    /// assert_eq!(iter.next(), Some((array![[1, 2], [3, 4]], array![0, 1])));
+    /// ```
+    pub fn iter_ref<'dataset>(&'dataset self) -> SingleProcessDataLoaderRefIter<'dataset, D, B, C>
+    where
+        C: Collate<D::SampleRef<'dataset>>,
+        D::SampleRef<'dataset>: Send,
+    {
+        let (sampler_iter, seed, num_yielded) = self.start_iteration();
+        SingleProcessDataLoaderRefIter {
+            sampler_iter,
+            num_yielded,
+            epoch: self.epoch,
+            seed,
+            data_fetcher: MapDatasetRefFetcher {
+                dataset: &self.dataset,
+                collate_fn: &self.collate_fn,
+                #[cfg(feature = "rayon")]
+                thread_pool: &self.thread_pool.0,
+            },
+        }
+    }
+}
+
+impl<D, B: BatchSampling, C> DataLoader<D, B, C> {
+    /// Start an iteration over the batch sampler, resuming from the loaded state if it matches the current epoch.
+    ///
+    /// Return the batch iterator, the seed of the iteration and the number of batches already yielded.
+    fn start_iteration(&self) -> (B::Iter, u64, u64) {
+        let resume_state = self.resume_state.take();
+        // The seed is always known, so that the iteration can be resumed.
+        let seed = match (resume_state, self.seed) {
+            (Some(state), _) => state.seed,
+            (None, Some(seed)) => seed,
+            (None, None) => rand::rng().random(),
+        };
+        let mut sampler_iter = self
+            .batch_sampler
+            .iter_with_rng(&mut rng_from_seed(Some(seed), self.epoch));
+        let num_yielded = resume_state.map_or(0, |state| state.num_yielded);
+        // Only the indices of the batches already yielded are skipped, the samples are not fetched.
+        for _ in 0..num_yielded {
+            sampler_iter.next();
+        }
+        (sampler_iter, seed, num_yielded)
+    }
+}
+
+impl<D, B, C> Len for DataLoader<D, B, C>
+where
+    B: BatchSampling,
 {
     /// Return the number of batch that contain the dataloader.
     fn len(&self) -> usize {
@@ -72,37 +303,55 @@ where
 
 /// Iterate over the dataloader with a single thread.
 #[derive(Debug)]
-pub struct SingleProcessDataLoaderIter<'dataset, D, S = SequentialSampler, C = DefaultCollate>
+pub struct SingleProcessDataLoaderIter<'dataset, D, B = BatchSampler, C = DefaultCollate>
 where
     D: Dataset + Sync,
-    S: Sampler,
+    B: BatchSampling,
     C: Collate<D::Sample>,
 {
     /// The batch iterator of this iterator.
-    sampler_iter: BatchIterator<S::IntoIter>,
-    /// Number of sample yielded.
+    sampler_iter: B::Iter,
+    /// Number of batch yielded, including the ones yielded before a checkpoint.
     num_yielded: u64,
+    /// The epoch of this iteration.
+    epoch: u64,
+    /// The seed of the random number generator given to the batch sampler.
+    seed: u64,
     /// Used to fetch the data from the dataset.
     data_fetcher: MapDatasetFetcher<'dataset, D, C>,
 }
 
-impl<'dataset, D, S, C> SingleProcessDataLoaderIter<'dataset, D, S, C>
+impl<D, B, C> SingleProcessDataLoaderIter<'_, D, B, C>
 where
     D: Dataset + Sync,
-    S: Sampler,
+    B: BatchSampling,
     C: Collate<D::Sample>,
     D::Sample: Send,
 {
-    fn new(loader: &DataLoader<D, S, C>) -> SingleProcessDataLoaderIter<'_, D, S, C> {
+    fn new(loader: &DataLoader<D, B, C>) -> SingleProcessDataLoaderIter<'_, D, B, C> {
+        let (sampler_iter, seed, num_yielded) = loader.start_iteration();
         SingleProcessDataLoaderIter {
-            sampler_iter: loader.batch_sampler.iter(),
-            num_yielded: 0,
+            sampler_iter,
+            num_yielded,
+            epoch: loader.epoch,
+            seed,
             data_fetcher: MapDatasetFetcher {
                 dataset: &loader.dataset,
                 collate_fn: &loader.collate_fn,
+                #[cfg(feature = "rayon")]
+                thread_pool: &loader.thread_pool.0,
             },
         }
     }
+
+    /// Return the progress of this iteration, to resume it later with [`DataLoader::load_state_dict`].
+    pub fn state_dict(&self) -> DataLoaderState {
+        DataLoaderState {
+            epoch: self.epoch,
+            seed: self.seed,
+            num_yielded: self.num_yielded,
+        }
+    }
     fn next_index(&mut self) -> Option<Vec<usize>> {
         self.sampler_iter.next()
     }
@@ -116,10 +365,10 @@ where
     }
 }
 
-impl<'dataset, D, S, C> Iterator for SingleProcessDataLoaderIter<'dataset, D, S, C>
+impl<D, B, C> Iterator for SingleProcessDataLoaderIter<'_, D, B, C>
 where
     D: Dataset + Sync,
-    S: Sampler,
+    B: BatchSampling,
     C: Collate<D::Sample>,
     D::Sample: Send,
 {
@@ -139,43 +388,112 @@ where
     }
 }
 
-impl<'dataset, D, S, C> IntoIterator for &'dataset DataLoader<D, S, C>
+impl<'dataset, D, B, C> IntoIterator for &'dataset DataLoader<D, B, C>
 where
     D: Dataset + Sync,
-    S: Sampler,
+    B: BatchSampling,
     C: Collate<D::Sample>,
     D::Sample: Send,
 {
     type Item = C::Output;
-    type IntoIter = SingleProcessDataLoaderIter<'dataset, D, S, C>;
+    type IntoIter = SingleProcessDataLoaderIter<'dataset, D, B, C>;
 
     fn into_iter(self) -> Self::IntoIter {
         self.iter()
     }
 }
 
-impl<'dataset, D, S, C> ExactSizeIterator for SingleProcessDataLoaderIter<'dataset, D, S, C>
+impl<D, B, C> ExactSizeIterator for SingleProcessDataLoaderIter<'_, D, B, C>
 where
     D: Dataset + Sync,
-    S: Sampler,
-    S::IntoIter: ExactSizeIterator,
+    B: BatchSampling,
+    B::Iter: ExactSizeIterator,
     C: Collate<D::Sample>,
     D::Sample: Send,
 {
 }
 
+/// Iterate over the dataloader with a single thread, giving borrowed samples to the collate function.
+///
+/// It is returned by [`DataLoader::iter_ref`].
+#[derive(Debug)]
+pub struct SingleProcessDataLoaderRefIter<'dataset, D, B = BatchSampler, C = DefaultCollate>
+where
+    B: BatchSampling,
+{
+    /// The batch iterator of this iterator.
+    sampler_iter: B::Iter,
+    /// Number of batch yielded, including the ones yielded before a checkpoint.
+    num_yielded: u64,
+    /// The epoch of this iteration.
+    epoch: u64,
+    /// The seed of the random number generator given to the batch sampler.
+    seed: u64,
+    /// Used to fetch the borrowed data from the dataset.
+    data_fetcher: MapDatasetRefFetcher<'dataset, D, C>,
+}
+
+impl<D, B: BatchSampling, C> SingleProcessDataLoaderRefIter<'_, D, B, C> {
+    /// Return the progress of this iteration, to resume it later with [`DataLoader::load_state_dict`].
+    pub fn state_dict(&self) -> DataLoaderState {
+        DataLoaderState {
+            epoch: self.epoch,
+            seed: self.seed,
+            num_yielded: self.num_yielded,
+        }
+    }
+}
+
+impl<'dataset, D, B, C> Iterator for SingleProcessDataLoaderRefIter<'dataset, D, B, C>
+where
+    D: GetSampleRef + Sync,
+    B: BatchSampling,
+    C: Collate<D::SampleRef<'dataset>>,
+    D::SampleRef<'dataset>: Send,
+{
+    type Item = C::Output;
+    fn next(&mut self) -> Option<Self::Item> {
+        let index = self.sampler_iter.next()?;
+        self.num_yielded += 1;
+        Some(self.data_fetcher.fetch(index))
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.sampler_iter.size_hint()
+    }
+}
+
+impl<'dataset, D, B, C> ExactSizeIterator for SingleProcessDataLoaderRefIter<'dataset, D, B, C>
+where
+    D: GetSampleRef + Sync,
+    B: BatchSampling,
+    B::Iter: ExactSizeIterator,
+    C: Collate<D::SampleRef<'dataset>>,
+    D::SampleRef<'dataset>: Send,
+{
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::collate::NoOpCollate;
+    use crate::sampler::BlockShuffleSampler;
+    use crate::sampler::BucketBatchSampler;
+    use crate::sampler::ClassBalancedBatchSampler;
+    use crate::sampler::DistributedSampler;
+    use crate::sampler::PrecomputedBatches;
     use crate::sampler::RandomSampler;
+    use crate::sampler::Sampler;
     use crate::sampler::SequentialSampler;
+    use crate::sampler::WeightedRandomSampler;
+    use crate::sampler::{SubsetRandomSampler, SubsetSequentialSampler};
+    use crate::GetSample;
     use crate::Len;
     use crate::NdarrayDataset;
-    use ndarray::{arr0, array, Array, Array1, Array4, Axis, Ix1, Ix4, Slice};
+    use ndarray::{arr0, array, Array, Array1, Array4, Axis, Slice};
     use ndarray_rand::rand_distr::{Normal, Uniform};
     use ndarray_rand::RandomExt;
     use std::collections::HashMap;
+    use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
 
     #[test]
     fn len() {
@@ -192,6 +510,410 @@ mod tests {
         assert_eq!(iter.len(), 4);
     }
 
+    #[test]
+    #[cfg(feature = "rayon")]
+    fn seed() {
+        let dataset: Vec<_> = (0..100).collect();
+        let first: Vec<_> = DataLoader::builder(dataset.clone())
+            .shuffle()
+            .seed(42)
+            .batch_size(8)
+            .collate_fn(NoOpCollate)
+            .num_threads(1)
+            .build()
+            .iter()
+            .collect();
+        let loader = DataLoader::builder(dataset)
+            .seed(42)
+            .shuffle()
+            .batch_size(8)
+            .collate_fn(NoOpCollate)
+            .num_threads(4)
+            .build();
+        assert_eq!(loader.iter().collect::<Vec<_>>(), first);
+        // The same seed gives the same permutation for each iteration.
+        assert_eq!(loader.iter().collect::<Vec<_>>(), first);
+        assert_ne!(first.concat(), (0..100).collect::<Vec<_>>());
+    }
+
+    #[test]
+    #[cfg(feature = "rayon")]
+    fn thread_pool() {
+        /// Dataset whose samples are the number of threads of the pool fetching them.
+        struct NumThreads;
+        impl Len for NumThreads {
+            fn len(&self) -> usize {
+                4
+            }
+        }
+        impl GetSample for NumThreads {
+            type Sample = usize;
+            fn get_sample(&self, _index: usize) -> Self::Sample {
+                rayon::current_num_threads()
+            }
+        }
+        impl Dataset for NumThreads {}
+
+        // Each loader has its own pool.
+        let train_loader = DataLoader::builder(NumThreads)
+            .num_threads(3)
+            .batch_size(4)
+            .collate_fn(NoOpCollate)
+            .build();
+        let eval_loader = DataLoader::builder(NumThreads)
+            .num_threads(1)
+            .batch_size(4)
+            .collate_fn(NoOpCollate)
+            .build();
+        assert_eq!(train_loader.iter().next(), Some(vec![3; 4]));
+        assert_eq!(eval_loader.iter().next(), Some(vec![1; 4]));
+
+        // Or they share an injected one.
+        let thread_pool = std::sync::Arc::new(
+            rayon::ThreadPoolBuilder::new()
+                .num_threads(2)
+                .build()
+                .unwrap(),
+        );
+        let loader = DataLoader::builder(NumThreads)
+            .thread_pool(thread_pool)
+            .batch_size(4)
+            .collate_fn(NoOpCollate)
+            .build();
+        assert_eq!(loader.iter().next(), Some(vec![2; 4]));
+    }
+
+    #[test]
+    fn num_samples() {
+        let dataset = vec![1, 2, 3, 4, 5, 6, 7, 8, 9, 10];
+        let dataloader = DataLoader::builder(dataset.clone())
+            .batch_size(4)
+            .shuffle()
+            .replacement()
+            .num_samples(30)
+            .build();
+        assert_eq!(dataloader.len(), 8);
+        assert_eq!(dataloader.iter().len(), 8);
+        assert_eq!(
+            dataloader
+                .iter()
+                .map(|batch: Array1<i32>| batch.len())
+                .sum::<usize>(),
+            30
+        );
+
+        let dataloader = DataLoader::builder(dataset)
+            .batch_size(4)
+            .drop_last()
+            .shuffle()
+            .num_samples(6)
+            .build();
+        assert_eq!(dataloader.len(), 1);
+        assert_eq!(dataloader.iter().count(), 1);
+    }
+
+    #[test]
+    fn weighted_sampler() {
+        let dataset = vec![0, 1, 2, 3];
+        let dataloader = DataLoader::builder(dataset)
+            .batch_size(5)
+            .with_sampler(WeightedRandomSampler::new(vec![0., 1., 0., 1.], 20))
+            .build();
+        assert_eq!(dataloader.len(), 4);
+        for batch in &dataloader {
+            let batch: Array1<i32> = batch;
+            assert!(batch.iter().all(|label| *label == 1 || *label == 3));
+        }
+    }
+
+    #[test]
+    fn subset_sampler() {
+        let dataset = vec![0, 1, 2, 3, 4, 5, 6, 7, 8, 9];
+        let dataloader = DataLoader::builder(dataset.clone())
+            .batch_size(2)
+            .with_sampler(SubsetSequentialSampler::new(vec![9, 3, 5]))
+            .build();
+        assert_eq!(dataloader.len(), 2);
+        let mut iter = dataloader.iter();
+        assert_eq!(iter.next(), Some(array![9, 3]));
+        assert_eq!(iter.next(), Some(array![5]));
+        assert_eq!(iter.next(), None);
+
+        let dataloader = DataLoader::builder(dataset)
+            .with_sampler(SubsetRandomSampler::new(vec![9, 3, 5]))
+            .collate_fn(NoOpCollate)
+            .build();
+        assert_eq!(dataloader.len(), 3);
+        let mut samples = dataloader.iter().collect::<Vec<_>>().concat();
+        samples.sort_unstable();
+        assert_eq!(samples, vec![3, 5, 9]);
+    }
+
+    #[test]
+    fn set_epoch() {
+        let mut loader = DataLoader::builder((0..100).collect::<Vec<_>>())
+            .shuffle()
+            .seed(42)
+            .batch_size(10)
+            .collate_fn(NoOpCollate)
+            .build();
+        assert_eq!(loader.epoch(), 0);
+        let epoch_0: Vec<_> = loader.iter().collect();
+        loader.set_epoch(1);
+        assert_eq!(loader.epoch(), 1);
+        let epoch_1: Vec<_> = loader.iter().collect();
+        assert_ne!(epoch_0, epoch_1);
+        assert_eq!(loader.iter().collect::<Vec<_>>(), epoch_1);
+        loader.set_epoch(0);
+        assert_eq!(loader.iter().collect::<Vec<_>>(), epoch_0);
+    }
+
+    #[test]
+    fn state_dict() {
+        let mut loader = DataLoader::builder((0..20).collect::<Vec<_>>())
+            .shuffle()
+            .batch_size(3)
+            .collate_fn(NoOpCollate)
+            .build();
+        loader.set_epoch(4);
+        let mut iter = loader.iter();
+        iter.next();
+        iter.next();
+        let state = iter.state_dict();
+        assert_eq!(state.epoch, 4);
+        assert_eq!(state.num_yielded, 2);
+        let remaining: Vec<_> = iter.collect();
+        assert_eq!(remaining.len(), 5);
+
+        // The state goes through a checkpoint file.
+        let checkpoint = serde_json::to_string(&state).unwrap();
+        let state: DataLoaderState = serde_json::from_str(&checkpoint).unwrap();
+        let mut loader = DataLoader::builder((0..20).collect::<Vec<_>>())
+            .shuffle()
+            .batch_size(3)
+            .collate_fn(NoOpCollate)
+            .build();
+        loader.load_state_dict(state);
+        assert_eq!(loader.epoch(), 4);
+        let mut iter = loader.iter();
+        assert_eq!(iter.len(), 5);
+        assert_eq!(iter.by_ref().collect::<Vec<_>>(), remaining);
+        assert_eq!(iter.state_dict().num_yielded, 7);
+        // Only the first iteration resumes.
+        assert_eq!(loader.iter().len(), 7);
+
+        // The next epoch starts from the beginning, and so does the checkpointed one once left.
+        loader.set_epoch(5);
+        assert_eq!(loader.iter().len(), 7);
+        loader.set_epoch(4);
+        assert_eq!(loader.iter().len(), 7);
+    }
+
+    #[test]
+    fn dataset_set_epoch() {
+        /// Dataset whose samples depend on the epoch, like random augmentations.
+        struct Augmented {
+            samples: Vec<u64>,
+            epoch: AtomicU64,
+        }
+        impl Len for Augmented {
+            fn len(&self) -> usize {
+                self.samples.len()
+            }
+        }
+        impl GetSample for Augmented {
+            type Sample = u64;
+            fn get_sample(&self, index: usize) -> Self::Sample {
+                self.samples[index] + 100 * self.epoch.load(Ordering::Relaxed)
+            }
+        }
+        impl Dataset for Augmented {
+            fn set_epoch(&self, epoch: u64) {
+                self.epoch.store(epoch, Ordering::Relaxed);
+            }
+        }
+
+        let mut loader = DataLoader::builder(Augmented {
+            samples: vec![1, 2, 3],
+            epoch: AtomicU64::new(0),
+        })
+        .build();
+        assert_eq!(loader.iter().next(), Some(array![1]));
+        loader.set_epoch(2);
+        assert_eq!(loader.iter().next(), Some(array![201]));
+    }
+
+    #[test]
+    fn dataset_get_batch() {
+        /// Dataset reading whole batches at once, it counts the reads.
+        struct Batched {
+            num_reads: AtomicUsize,
+        }
+        impl Len for Batched {
+            fn len(&self) -> usize {
+                10
+            }
+        }
+        impl GetSample for Batched {
+            type Sample = usize;
+            fn get_sample(&self, _index: usize) -> Self::Sample {
+                unreachable!("the samples are read by batch")
+            }
+            fn get_batch(&self, indices: &[usize]) -> Option<Vec<Self::Sample>> {
+                self.num_reads.fetch_add(1, Ordering::Relaxed);
+                Some(indices.to_vec())
+            }
+        }
+        impl Dataset for Batched {}
+
+        let loader = DataLoader::builder(Batched {
+            num_reads: 0.into(),
+        })
+        .batch_size(4)
+        .collate_fn(NoOpCollate)
+        .build();
+        assert_eq!(
+            loader.iter().collect::<Vec<_>>(),
+            vec![vec![0, 1, 2, 3], vec![4, 5, 6, 7], vec![8, 9]]
+        );
+        // A single read per batch.
+        assert_eq!(loader.dataset.num_reads.load(Ordering::Relaxed), 3);
+    }
+
+    #[test]
+    fn iter_ref() {
+        let data = Array::from_shape_fn((10, 2, 3), |(i, j, k)| i * 100 + j * 10 + k);
+        let labels = Array::from_shape_fn(10, |i| i);
+        let dataset = NdarrayDataset::new((data.view(), labels));
+        let loader = DataLoader::builder(dataset)
+            .shuffle()
+            .seed(42)
+            .batch_size(4)
+            .build();
+        assert_eq!(loader.iter_ref().len(), 3);
+        assert!(loader.iter_ref().eq(loader.iter()));
+        let mut iter = loader.iter_ref();
+        iter.next();
+        assert_eq!(iter.state_dict().num_yielded, 1);
+
+        // The samples aren't cloned before the collate function.
+        let loader = DataLoader::builder(vec![String::from("a"), String::from("b")])
+            .batch_size(2)
+            .collate_fn(NoOpCollate)
+            .build();
+        let batch: Vec<&String> = loader.iter_ref().next().unwrap();
+        assert_eq!(batch, vec!["a", "b"]);
+    }
+
+    #[test]
+    fn distributed_sampler() {
+        let dataset: Vec<_> = (0..10).collect();
+        let mut loaders: Vec<_> = (0..2)
+            .map(|rank| {
+                DataLoader::builder(dataset.clone())
+                    .batch_size(2)
+                    .with_sampler(DistributedSampler::new(dataset.len(), 2, rank).seed(7))
+                    .collate_fn(NoOpCollate)
+                    .build()
+            })
+            .collect();
+        let epoch_0: Vec<_> = loaders
+            .iter()
+            .map(|loader| loader.iter().collect::<Vec<_>>().concat())
+            .collect();
+        assert_eq!(loaders[0].len(), 3);
+        let mut samples = epoch_0.concat();
+        samples.sort_unstable();
+        assert_eq!(samples, dataset);
+
+        for loader in &mut loaders {
+            loader.set_epoch(1);
+        }
+        let epoch_1: Vec<_> = loaders
+            .iter()
+            .map(|loader| loader.iter().collect::<Vec<_>>().concat())
+            .collect();
+        assert_ne!(epoch_0, epoch_1);
+    }
+
+    #[test]
+    fn custom_batch_sampler() {
+        let dataset: Vec<_> = (0..20).collect();
+        let mut batches = Vec::new();
+        for i in (0..20).step_by(5) {
+            batches.push((i..i + 2).collect::<Vec<_>>());
+            batches.push((i + 2..i + 5).collect::<Vec<_>>());
+        }
+        let dataloader = DataLoader::builder(dataset)
+            .with_batch_sampler(PrecomputedBatches(batches.clone()))
+            .collate_fn(NoOpCollate)
+            .build();
+        assert_eq!(dataloader.len(), 8);
+        let mut iter = dataloader.iter();
+        assert_eq!(iter.len(), 8);
+        assert_eq!(iter.next(), Some(vec![0, 1]));
+        assert_eq!(iter.len(), 7);
+        assert_eq!(iter.next(), Some(vec![2, 3, 4]));
+        assert_eq!(dataloader.iter().collect::<Vec<_>>(), batches);
+    }
+
+    #[test]
+    fn bucket_batch_sampler() {
+        let dataset = vec![
+            vec![1],
+            vec![1, 2, 3],
+            vec![4],
+            vec![4, 5, 6],
+            vec![7],
+            vec![7, 8, 9],
+        ];
+        let batch_sampler =
+            BucketBatchSampler::from_dataset(&dataset, |tokens| tokens.len(), &[2], 3);
+        let dataloader = DataLoader::builder(dataset)
+            .with_batch_sampler(batch_sampler)
+            .build();
+        assert_eq!(dataloader.len(), 2);
+        // The default collate function requires the samples of a batch to have the same length.
+        for batch in &dataloader {
+            let batch: Vec<Array1<i32>> = batch;
+            assert!(batch.len() == 1 || batch.len() == 3);
+            assert!(batch.iter().all(|column| column.len() == 3));
+        }
+    }
+
+    #[test]
+    fn class_balanced_batch_sampler() {
+        // The label of each sample is its tens digit.
+        let dataset: Vec<i32> = vec![10, 11, 12, 13, 20, 21, 22, 23, 30, 31];
+        let labels: Vec<i32> = dataset.iter().map(|sample| sample / 10).collect();
+        let batch_sampler = ClassBalancedBatchSampler::per_class(&labels, 2, 2);
+        let dataloader = DataLoader::builder(dataset)
+            .with_batch_sampler(batch_sampler)
+            .build();
+        assert_eq!(dataloader.len(), 2);
+        for batch in &dataloader {
+            assert_eq!(batch.len(), 4);
+            assert_eq!(batch[0] / 10, batch[1] / 10);
+            assert_eq!(batch[2] / 10, batch[3] / 10);
+            assert_ne!(batch[0] / 10, batch[2] / 10);
+        }
+    }
+
+    #[test]
+    fn block_shuffle_sampler() {
+        let dataset: Vec<_> = (0..40).collect();
+        let dataloader = DataLoader::builder(dataset)
+            .with_sampler(BlockShuffleSampler::new(40, 8))
+            .batch_size(8)
+            .collate_fn(NoOpCollate)
+            .build();
+        assert_eq!(dataloader.len(), 5);
+        // A batch is exactly one block.
+        for batch in &dataloader {
+            assert!(batch.iter().all(|sample| sample / 8 == batch[0] / 8));
+        }
+    }
+
     #[test]
     fn one_dimension_basic() {
         let dataset = vec![1, 2, 3, 4, 5, 6, 7, 8, 9, 10];
@@ -249,16 +971,18 @@ mod tests {
         assert_eq!(iter.next(), Some(vec![9, 10]));
         assert_eq!(iter.next(), None);
     }
+    type TestDataset = NdarrayDataset<(Array4<f64>, Array1<f64>)>;
     struct TestDataLoader<S: Sampler> {
-        loader: DataLoader<NdarrayDataset<f64, f64, Ix4, Ix1>, S>,
+        loader: DataLoader<TestDataset, BatchSampler<S>>,
         data: Array4<f64>,
         labels: Array1<f64>,
-        dataset: NdarrayDataset<f64, f64, Ix4, Ix1>,
+        dataset: TestDataset,
     }
     enum TestDataLoaderData {
         Sequential(TestDataLoader<SequentialSampler>),
         Random(TestDataLoader<RandomSampler>),
     }
+    #[allow(clippy::fn_params_excessive_bools)]
     fn get_loader_with_dummy_data(batch_size: usize, shuffle: bool) -> TestDataLoaderData {
         // We use a normal distribution for the random numbers
         let normal: Normal<f64> = Normal::new(0.0, 1.0).unwrap();
@@ -267,9 +991,7 @@ mod tests {
         // We create a 1-dimensional array populated with random value
         let labels = Array::random(100, Uniform::<f64>::new(0., 50.));
         // Basic Test dataset
-        let dataset = NdarrayDataset {
-            ndarrays: (data.clone(), labels.clone()),
-        };
+        let dataset = NdarrayDataset::new((data.clone(), labels.clone()));
 
         if shuffle {
             let loader = DataLoader::builder(dataset.clone())
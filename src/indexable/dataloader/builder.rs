@@ -1,41 +1,54 @@
 use crate::{
-    collate::{Collate, DefaultCollate},
-    sampler::{BatchSampler, RandomSampler, Sampler, SequentialSampler},
-    Dataset,
+    collate::{Collate, DefaultCollate, TryCollate},
+    sampler::{BatchSampler, BatchSampling, FromLen, RandomSampler, Sampler, SequentialSampler},
+    Dataset, TryGetSample,
 };
 
 #[cfg(feature = "rayon")]
-use crate::THREAD_POOL;
+use crate::SharedThreadPool;
+#[cfg(feature = "rayon")]
+use rayon::ThreadPool;
+use std::sync::Arc;
 
-use super::DataLoader;
+use super::{DataLoader, ErrorPolicy, ResumeState};
 
 /// Basic builder for creating dataloader from a type that implement `IntoIterator`.
 /// add a dataloader for all type that implement `IntoIterator`.
 /// If the iterator `Item` is not supported by default collate, you must provide your own collate function
 #[must_use]
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Hash, Ord)]
-pub struct Builder<D, S = SequentialSampler, C = DefaultCollate>
+pub struct Builder<D, B = BatchSampler, C = DefaultCollate>
 where
     D: Dataset,
-    S: Sampler,
-    C: Collate<D::Sample>,
+    B: BatchSampling,
 {
     /// The dataset from which the loader will yield the data.
     dataset: D,
     /// The sampler used to gather elements of the batch together.
-    batch_sampler: BatchSampler<S>,
+    batch_sampler: B,
     /// Used to collate the data together.
     collate_fn: C,
     #[cfg(feature = "rayon")]
     /// Number of threads to use.
     num_threads: usize,
+    #[cfg(feature = "rayon")]
+    /// Thread pool given with [`Builder::thread_pool`], instead of creating one.
+    thread_pool: Option<SharedThreadPool>,
+    /// Seed of the random number generator given to the sampler.
+    seed: Option<u64>,
+    /// Number of background threads of [`DataLoader::iter_prefetch`].
+    num_workers: usize,
+    /// Number of batches fetched in advance by each worker of [`DataLoader::iter_prefetch`].
+    prefetch_factor: usize,
+    /// Whether [`DataLoader::iter_prefetch`] yields the batches in the order of the batch sampler.
+    in_order: bool,
+    /// How [`DataLoader::try_iter`] handles the samples that can't be loaded.
+    error_policy: ErrorPolicy,
 }
 
-// FIXME: kind of strange that we require DefaultCollatte even if in the end we may won't use it
-impl<D> Builder<D, SequentialSampler, DefaultCollate>
+impl<D> Builder<D, BatchSampler, DefaultCollate>
 where
     D: Dataset,
-    DefaultCollate: Collate<D::Sample>,
 {
     /// Create a new [`Builder`], with default fields.
     /// By default the [`Builder`] is sequential and have a `batch_size` of one.
@@ -56,45 +69,122 @@ where
             collate_fn: DefaultCollate,
             #[cfg(feature = "rayon")]
             num_threads,
+            #[cfg(feature = "rayon")]
+            thread_pool: None,
+            seed: None,
+            num_workers: 1,
+            prefetch_factor: 2,
+            in_order: true,
+            error_policy: ErrorPolicy::Stop,
         }
     }
 }
 
-impl<D, S, C> Builder<D, S, C>
+impl<D, B, C> Builder<D, B, C>
 where
     D: Dataset,
-    S: Sampler,
-    C: Collate<D::Sample>,
+    B: BatchSampling,
 {
-    /// Use a random sampler.
-    pub fn shuffle(self) -> Builder<D, RandomSampler, C> {
-        self.sampler::<RandomSampler>()
-    }
-    /// Set the number of elements in a batch.
-    pub fn batch_size(mut self, batch_size: usize) -> Self {
-        self.batch_sampler.batch_size = batch_size;
+    /// Seed the random number generator given to the sampler, making the shuffling reproducible.
+    ///
+    /// With the same seed, the indices are yielded in the same order regardless of the number of threads.
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
         self
     }
 
-    /// Set the number of threads to use.
+    /// Set the number of threads to use. The loader creates its own thread pool with this number of threads.
     #[cfg(feature = "rayon")]
     pub fn num_threads(mut self, num_threads: usize) -> Self {
         self.num_threads = num_threads;
+        self.thread_pool = None;
         self
     }
 
-    /// Drop the lasts element if they don't feat into a batch. For instance if a dataset have 13
-    /// samples and a `batch_size` of 5, the last 3 samples will be dropped.
-    pub fn drop_last(mut self) -> Self {
-        self.batch_sampler.drop_last = true;
+    /// Use an existing thread pool instead of creating one, for instance to share it between several loaders.
+    /// It replaces the `num_threads` option.
+    ///
+    /// ```
+    /// use ai_dataloader::indexable::DataLoader;
+    /// use std::sync::Arc;
+    ///
+    /// let thread_pool = Arc::new(rayon::ThreadPoolBuilder::new().num_threads(2).build().unwrap());
+    /// let train_loader = DataLoader::builder(vec![1, 2, 3, 4])
+    ///     .thread_pool(Arc::clone(&thread_pool))
+    ///     .build();
+    /// let eval_loader = DataLoader::builder(vec![5, 6])
+    ///     .thread_pool(thread_pool)
+    ///     .build();
+    /// ```
+    #[cfg(feature = "rayon")]
+    pub fn thread_pool(mut self, thread_pool: Arc<ThreadPool>) -> Self {
+        self.thread_pool = Some(SharedThreadPool(thread_pool));
+        self
+    }
+
+    /// Set the number of background threads of [`DataLoader::iter_prefetch`], each fetching and collating
+    /// whole batches. By default there is one worker.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `num_workers` is zero.
+    pub fn num_workers(mut self, num_workers: usize) -> Self {
+        assert!(num_workers > 0, "num_workers should be positive");
+        self.num_workers = num_workers;
+        self
+    }
+
+    /// Set the number of batches fetched in advance by each worker of [`DataLoader::iter_prefetch`],
+    /// two by default. At most `num_workers * prefetch_factor` batches are loaded at the same time.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `prefetch_factor` is zero.
+    pub fn prefetch_factor(mut self, prefetch_factor: usize) -> Self {
+        assert!(prefetch_factor > 0, "prefetch_factor should be positive");
+        self.prefetch_factor = prefetch_factor;
+        self
+    }
+
+    /// Set whether [`DataLoader::iter_prefetch`] yields the batches in the order of the batch sampler, the default.
+    ///
+    /// With `false`, each batch is yielded as soon as a worker has loaded it, so that a slow batch doesn't hold up
+    /// the ones behind it. The index of each batch in the batch sampler is given by the `batch_index` method
+    /// of the iterator.
+    #[allow(clippy::fn_params_excessive_bools)]
+    pub fn in_order(mut self, in_order: bool) -> Self {
+        self.in_order = in_order;
+        self
+    }
+
+    /// Set how [`DataLoader::try_iter`] handles the samples that can't be loaded, by default it stops at the first error.
+    pub fn error_policy(mut self, error_policy: ErrorPolicy) -> Self {
+        self.error_policy = error_policy;
         self
     }
 
     /// Set a custom collate function.
-    pub fn collate_fn<CF>(self, collate_fn: CF) -> Builder<D, S, CF>
+    pub fn collate_fn<CF>(self, collate_fn: CF) -> Builder<D, B, CF>
     where
         CF: Collate<D::Sample>,
     {
+        self.with_collate_fn(collate_fn)
+    }
+
+    /// Set a custom collate function for a fallible dataset, collating the samples successfully loaded
+    /// by [`DataLoader::try_iter`].
+    ///
+    /// A [`Collate`] that can't fail is wrapped in [`Infallible`](crate::collate::Infallible).
+    pub fn try_collate_fn<CF>(self, collate_fn: CF) -> Builder<D, B, CF>
+    where
+        D: TryGetSample,
+        CF: TryCollate<D::Output>,
+    {
+        self.with_collate_fn(collate_fn)
+    }
+
+    /// Replace the collate function, whatever the samples it collates.
+    fn with_collate_fn<CF>(self, collate_fn: CF) -> Builder<D, B, CF> {
         Builder {
             dataset: self.dataset,
 
@@ -102,73 +192,136 @@ where
             collate_fn,
             #[cfg(feature = "rayon")]
             num_threads: self.num_threads,
+            #[cfg(feature = "rayon")]
+            thread_pool: self.thread_pool,
+            seed: self.seed,
+            num_workers: self.num_workers,
+            prefetch_factor: self.prefetch_factor,
+            in_order: self.in_order,
+            error_policy: self.error_policy,
         }
     }
 
-    /// Set a custom [`Sampler`].
-    pub fn sampler<SA>(self) -> Builder<D, SA, C>
+    /// Set a custom batch sampler, yielding batches of indices of any size or makeup.
+    ///
+    /// It replaces the `batch_size`, `drop_last` and `sampler` options.
+    pub fn with_batch_sampler<BS>(self, batch_sampler: BS) -> Builder<D, BS, C>
     where
-        SA: Sampler,
+        BS: BatchSampling,
     {
-        let sampler: SA = SA::new(self.dataset.len());
         Builder {
             dataset: self.dataset,
-            batch_sampler: BatchSampler {
-                sampler,
-                batch_size: self.batch_sampler.batch_size,
-                drop_last: self.batch_sampler.drop_last,
-            },
-
+            batch_sampler,
             collate_fn: self.collate_fn,
             #[cfg(feature = "rayon")]
             num_threads: self.num_threads,
+            #[cfg(feature = "rayon")]
+            thread_pool: self.thread_pool,
+            seed: self.seed,
+            num_workers: self.num_workers,
+            prefetch_factor: self.prefetch_factor,
+            in_order: self.in_order,
+            error_policy: self.error_policy,
         }
     }
+
     /// Create a `Dataloader` from a [`Builder`].
-    pub fn build(self) -> DataLoader<D, S, C> {
+    pub fn build(self) -> DataLoader<D, B, C> {
         #[cfg(feature = "rayon")]
-        match THREAD_POOL.get() {
-            Some(pool) => {
-                if pool.current_num_threads() != self.num_threads {
-                    // We reset the threadpool because we can't modify the number of
-                    // threads of an existing thread pool.
-                    #[cfg(feature = "rayon")]
-                    THREAD_POOL.set(
-                        rayon::ThreadPoolBuilder::new()
-                            .num_threads(self.num_threads)
-                            .build()
-                            .expect("could not spawn threads"),
-                    )
-                } else {
-                    THREAD_POOL.set(
-                        rayon::ThreadPoolBuilder::new()
-                            .num_threads(self.num_threads)
-                            .build()
-                            .expect("could not spawn threads"),
-                    )
-                }
-            }
-            _ => THREAD_POOL.set(
+        let thread_pool = self.thread_pool.unwrap_or_else(|| {
+            SharedThreadPool(Arc::new(
                 rayon::ThreadPoolBuilder::new()
                     .num_threads(self.num_threads)
                     .build()
                     .expect("could not spawn threads"),
-            ),
-        }
-        .ok();
+            ))
+        });
 
         DataLoader {
-            dataset: self.dataset,
+            dataset: Arc::new(self.dataset),
             batch_sampler: self.batch_sampler,
-            collate_fn: self.collate_fn,
+            collate_fn: Arc::new(self.collate_fn),
+            seed: self.seed,
+            num_workers: self.num_workers,
+            prefetch_factor: self.prefetch_factor,
+            in_order: self.in_order,
+            error_policy: self.error_policy,
+            #[cfg(feature = "rayon")]
+            thread_pool,
+            epoch: 0,
+            resume_state: ResumeState::default(),
         }
     }
 }
 
+impl<D, S, C> Builder<D, BatchSampler<S>, C>
+where
+    D: Dataset,
+    S: Sampler,
+{
+    /// Use a random sampler.
+    pub fn shuffle(self) -> Builder<D, BatchSampler<RandomSampler>, C> {
+        self.sampler::<RandomSampler>()
+    }
+    /// Set the number of elements in a batch.
+    pub fn batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_sampler.batch_size = batch_size;
+        self
+    }
+
+    /// Drop the lasts element if they don't feat into a batch. For instance if a dataset have 13
+    /// samples and a `batch_size` of 5, the last 3 samples will be dropped.
+    pub fn drop_last(mut self) -> Self {
+        self.batch_sampler.drop_last = true;
+        self
+    }
+
+    /// Set a custom [`Sampler`], created from the length of the dataset.
+    pub fn sampler<SA>(self) -> Builder<D, BatchSampler<SA>, C>
+    where
+        SA: FromLen,
+    {
+        let sampler = SA::from_len(self.dataset.len());
+        self.with_sampler(sampler)
+    }
+
+    /// Set a custom [`Sampler`] instance. Unlike [`Builder::sampler`], the sampler can hold any state,
+    /// like a list of indices or weights.
+    pub fn with_sampler<SA>(self, sampler: SA) -> Builder<D, BatchSampler<SA>, C>
+    where
+        SA: Sampler,
+    {
+        let batch_sampler = BatchSampler {
+            sampler,
+            batch_size: self.batch_sampler.batch_size,
+            drop_last: self.batch_sampler.drop_last,
+        };
+        self.with_batch_sampler(batch_sampler)
+    }
+}
+
+impl<D, C> Builder<D, BatchSampler<RandomSampler>, C>
+where
+    D: Dataset,
+{
+    /// Draw the samples with replacement: the same sample can be yielded several times during one iteration.
+    pub fn replacement(mut self) -> Self {
+        self.batch_sampler.sampler = self.batch_sampler.sampler.replacement();
+        self
+    }
+    /// Set the number of samples yielded by one iteration, which can be more or less than the dataset length.
+    /// By default all the dataset is sampled.
+    pub fn num_samples(mut self, num_samples: usize) -> Self {
+        self.batch_sampler.sampler = self.batch_sampler.sampler.num_samples(num_samples);
+        self
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::collate::NoOpCollate;
+    use crate::sampler::{PrecomputedBatches, WeightedRandomSampler};
 
     #[test]
     fn api() {
@@ -213,6 +366,32 @@ mod tests {
             .batch_size(2)
             .build();
 
+        let _loader = Builder::new(vec![1, 2, 3, 4]).shuffle().seed(42).build();
+
+        let _loader = Builder::new(vec![1, 2, 3, 4])
+            .batch_size(2)
+            .with_sampler(WeightedRandomSampler::new(vec![1., 2., 3., 4.], 10))
+            .build();
+
+        let _loader = Builder::new(vec![1, 2, 3, 4])
+            .with_batch_sampler(PrecomputedBatches(vec![vec![0, 1, 2], vec![3]]))
+            .collate_fn(NoOpCollate)
+            .build();
+
+        let _loader = Builder::new(vec![1, 2, 3, 4])
+            .shuffle()
+            .replacement()
+            .num_samples(10)
+            .batch_size(3)
+            .build();
+
+        let _loader = Builder::new(vec![1, 2, 3, 4])
+            .num_workers(4)
+            .prefetch_factor(3)
+            .in_order(false)
+            .error_policy(ErrorPolicy::Retry(2))
+            .build();
+
         let _loader = Builder::new(vec![1, 2, 3, 4])
             .collate_fn(|x| x)
             .batch_size(2)
@@ -0,0 +1,549 @@
+//! Iterate over the dataloader with background threads fetching the batches in advance.
+
+use std::collections::{BTreeSet, HashMap};
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender, SyncSender};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::thread::{self, JoinHandle};
+
+use super::{DataLoader, DataLoaderState};
+use crate::indexable::fetch::get_batch;
+use crate::{
+    collate::{Collate, DefaultCollate},
+    sampler::{BatchSampler, BatchSampling},
+    Dataset,
+};
+
+/// Batch of indices sent to a worker, with its position in the iteration.
+type Task = (u64, Vec<usize>);
+
+/// Collated batch sent back by a worker, or the panic of the worker.
+type TaskResult<O> = (u64, thread::Result<O>);
+
+impl<D, B, C> DataLoader<D, B, C>
+where
+    D: Dataset + Send + Sync + 'static,
+    B: BatchSampling,
+    C: Collate<D::Sample> + Send + Sync + 'static,
+    C::Output: Send + 'static,
+{
+    /// Return an iterator whose `num_workers` background threads fetch and collate whole batches in advance,
+    /// overlapping the data loading with the computation on the previous batches.
+    ///
+    /// Up to `num_workers * prefetch_factor` batches are loaded ahead, both are set with the builder options of the same
+    /// name. The workers take the batches from a shared queue, so that a slow batch only holds up its own worker.
+    /// The batches are yielded in the order of the batch sampler, or as soon as they are ready with the `in_order(false)`
+    /// builder option. The iteration can be checkpointed as with [`DataLoader::iter`].
+    ///
+    /// The workers share the dataset and the collate function of the loader, they aren't copied. The iterator panics
+    /// if a worker panics, for instance if the samples can't be collated, use [`DataLoader::try_iter`] to get the
+    /// error instead.
+    ///
+    /// ```
+    /// use ai_dataloader::indexable::DataLoader;
+    ///
+    /// let loader = DataLoader::builder(vec![1, 2, 3, 4, 5])
+    ///     .batch_size(2)
+    ///     .num_workers(2)
+    ///     .prefetch_factor(4)
+    ///     .build();
+    /// assert!(loader.iter_prefetch().eq(loader.iter()));
+    /// ```
+    pub fn iter_prefetch(&self) -> MultiWorkerDataLoaderIter<D, B, C> {
+        let (sampler_iter, seed, num_yielded) = self.start_iteration();
+        let max_in_flight = self.num_workers * self.prefetch_factor;
+        // A worker never waits to send its batch: there is room for all the batches in flight.
+        let (result_sender, results) = mpsc::sync_channel(max_in_flight);
+        let stop = Arc::new(AtomicBool::new(false));
+        let (task_sender, tasks) = mpsc::channel();
+        let tasks = Arc::new(Mutex::new(tasks));
+        let workers = (0..self.num_workers)
+            .map(|_| {
+                spawn_worker(
+                    Arc::clone(&self.dataset),
+                    Arc::clone(&self.collate_fn),
+                    Arc::clone(&tasks),
+                    result_sender.clone(),
+                    Arc::clone(&stop),
+                )
+            })
+            .collect();
+        let mut iter = MultiWorkerDataLoaderIter {
+            sampler_iter,
+            task_sender: Some(task_sender),
+            results,
+            workers,
+            stop,
+            reorder_buffer: HashMap::new(),
+            in_order: self.in_order,
+            max_in_flight,
+            num_sent: num_yielded,
+            num_yielded,
+            num_completed: num_yielded,
+            yielded_ahead: BTreeSet::new(),
+            batch_index: None,
+            epoch: self.epoch,
+            seed,
+        };
+        iter.fill();
+        iter
+    }
+}
+
+/// Spawn a worker fetching and collating the batches of `tasks`, until the iterator drops its sender or sets `stop`.
+///
+/// The queue of tasks is shared by all the workers, an idle worker takes the next batch.
+fn spawn_worker<D, C>(
+    dataset: Arc<D>,
+    collate_fn: Arc<C>,
+    tasks: Arc<Mutex<Receiver<Task>>>,
+    results: SyncSender<TaskResult<C::Output>>,
+    stop: Arc<AtomicBool>,
+) -> JoinHandle<()>
+where
+    D: Dataset + Send + Sync + 'static,
+    C: Collate<D::Sample> + Send + Sync + 'static,
+    C::Output: Send + 'static,
+{
+    thread::spawn(move || {
+        loop {
+            // The queue is only locked while waiting for a task, not while loading it.
+            let task = tasks.lock().unwrap_or_else(PoisonError::into_inner).recv();
+            let Ok((batch_idx, indices)) = task else {
+                return;
+            };
+            if stop.load(Ordering::Relaxed) {
+                return;
+            }
+            // The panic is forwarded to the iterator, which would otherwise wait for the batch forever.
+            let batch = panic::catch_unwind(AssertUnwindSafe(|| {
+                collate_fn.collate(get_batch(&*dataset, &indices))
+            }));
+            if results.send((batch_idx, batch)).is_err() {
+                return;
+            }
+        }
+    })
+}
+
+/// Iterate over the dataloader with background threads, it is returned by [`DataLoader::iter_prefetch`].
+///
+/// The workers are stopped when the iterator is dropped.
+#[derive(Debug)]
+pub struct MultiWorkerDataLoaderIter<D, B = BatchSampler, C = DefaultCollate>
+where
+    D: Dataset,
+    B: BatchSampling,
+    C: Collate<D::Sample>,
+{
+    /// The batch iterator of this iterator.
+    sampler_iter: B::Iter,
+    /// Send the batches of indices to the queue shared by the workers, `None` once the iterator is dropped.
+    task_sender: Option<Sender<Task>>,
+    /// Receive the collated batches from all the workers.
+    results: Receiver<TaskResult<C::Output>>,
+    /// The worker threads.
+    workers: Vec<JoinHandle<()>>,
+    /// Tell the workers to stop before their next batch, without loading the ones already sent to them.
+    stop: Arc<AtomicBool>,
+    /// The batches received before the ones preceding them in the iteration.
+    reorder_buffer: HashMap<u64, C::Output>,
+    /// Whether the batches are yielded in the order of the batch sampler.
+    in_order: bool,
+    /// Maximum number of batches sent to the workers and not yet yielded.
+    max_in_flight: usize,
+    /// Number of batches sent to the workers, including the ones yielded before a checkpoint.
+    num_sent: u64,
+    /// Number of batch yielded, including the ones yielded before a checkpoint.
+    num_yielded: u64,
+    /// Number of batches at the beginning of the iteration that have all been yielded.
+    num_completed: u64,
+    /// The batches yielded before some of the batches preceding them, when out of order.
+    yielded_ahead: BTreeSet<u64>,
+    /// Index in the batch sampler of the last batch yielded.
+    batch_index: Option<u64>,
+    /// The epoch of this iteration.
+    epoch: u64,
+    /// The seed of the random number generator given to the batch sampler.
+    seed: u64,
+}
+
+impl<D, B, C> MultiWorkerDataLoaderIter<D, B, C>
+where
+    D: Dataset,
+    B: BatchSampling,
+    C: Collate<D::Sample>,
+{
+    /// Return the progress of this iteration, to resume it later with [`DataLoader::load_state_dict`].
+    ///
+    /// The batches loaded in advance and not yet yielded are loaded again by the resumed iteration.
+    /// Out of order, the resumed iteration starts at the first batch not yet yielded,
+    /// so the batches yielded ahead of it are yielded again.
+    pub fn state_dict(&self) -> DataLoaderState {
+        DataLoaderState {
+            epoch: self.epoch,
+            seed: self.seed,
+            num_yielded: self.num_completed,
+        }
+    }
+
+    /// Index in the batch sampler of the last batch yielded, it differs from the number of batches yielded
+    /// when the batches are yielded out of order.
+    ///
+    /// ```
+    /// use ai_dataloader::collate::NoOpCollate;
+    /// use ai_dataloader::indexable::DataLoader;
+    ///
+    /// let loader = DataLoader::builder(vec![10_u64, 11, 12, 13])
+    ///     .num_workers(2)
+    ///     .in_order(false)
+    ///     .collate_fn(NoOpCollate)
+    ///     .build();
+    /// let mut iter = loader.iter_prefetch();
+    /// assert_eq!(iter.batch_index(), None);
+    /// while let Some(batch) = iter.next() {
+    ///     let batch_index = iter.batch_index().unwrap();
+    ///     assert_eq!(batch, vec![10 + batch_index]);
+    /// }
+    /// ```
+    pub fn batch_index(&self) -> Option<u64> {
+        self.batch_index
+    }
+
+    /// Record that the batch at `batch_index` is yielded.
+    fn mark_yielded(&mut self, batch_index: u64) {
+        self.num_yielded += 1;
+        self.batch_index = Some(batch_index);
+        if batch_index != self.num_completed {
+            self.yielded_ahead.insert(batch_index);
+            return;
+        }
+        self.num_completed += 1;
+        while self.yielded_ahead.remove(&self.num_completed) {
+            self.num_completed += 1;
+        }
+    }
+
+    /// Number of batches sent to the workers and not yet yielded.
+    fn num_in_flight(&self) -> usize {
+        usize::try_from(self.num_sent - self.num_yielded)
+            .expect("the batches in flight are bounded")
+    }
+
+    /// Send batches of indices to the workers until `max_in_flight` batches are in flight.
+    fn fill(&mut self) {
+        while self.num_in_flight() < self.max_in_flight {
+            let Some(indices) = self.sampler_iter.next() else {
+                return;
+            };
+            self.task_sender
+                .as_ref()
+                .expect("the tasks are sent until the iterator is dropped")
+                .send((self.num_sent, indices))
+                .expect("a worker stopped unexpectedly");
+            self.num_sent += 1;
+        }
+    }
+}
+
+impl<D, B, C> Iterator for MultiWorkerDataLoaderIter<D, B, C>
+where
+    D: Dataset,
+    B: BatchSampling,
+    C: Collate<D::Sample>,
+{
+    type Item = C::Output;
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.num_in_flight() == 0 {
+            return None;
+        }
+        let (batch_idx, batch) = loop {
+            if let Some(batch) = self.reorder_buffer.remove(&self.num_completed) {
+                break (self.num_completed, batch);
+            }
+            let (batch_idx, batch) = self
+                .results
+                .recv()
+                .expect("all the workers stopped unexpectedly");
+            let batch = match batch {
+                Ok(batch) => batch,
+                Err(payload) => panic::resume_unwind(payload),
+            };
+            if !self.in_order {
+                break (batch_idx, batch);
+            }
+            self.reorder_buffer.insert(batch_idx, batch);
+        };
+        self.mark_yielded(batch_idx);
+        self.fill();
+        Some(batch)
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let (lower, upper) = self.sampler_iter.size_hint();
+        let num_in_flight = self.num_in_flight();
+        (
+            lower.saturating_add(num_in_flight),
+            upper.and_then(|upper| upper.checked_add(num_in_flight)),
+        )
+    }
+}
+
+impl<D, B, C> ExactSizeIterator for MultiWorkerDataLoaderIter<D, B, C>
+where
+    D: Dataset,
+    B: BatchSampling,
+    B::Iter: ExactSizeIterator,
+    C: Collate<D::Sample>,
+{
+}
+
+impl<D, B, C> Drop for MultiWorkerDataLoaderIter<D, B, C>
+where
+    D: Dataset,
+    B: BatchSampling,
+    C: Collate<D::Sample>,
+{
+    fn drop(&mut self) {
+        // The workers finish their current batch and skip the ones queued.
+        self.stop.store(true, Ordering::Relaxed);
+        self.task_sender = None;
+        for worker in self.workers.drain(..) {
+            // The panics of the workers are already forwarded by `next`.
+            let _ = worker.join();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::collate::NoOpCollate;
+    use crate::GetSample;
+    use crate::Len;
+    use std::sync::Mutex;
+    use std::time::{Duration, Instant};
+
+    /// Dataset whose first samples are the slowest to load.
+    struct Slow;
+    impl Len for Slow {
+        fn len(&self) -> usize {
+            8
+        }
+    }
+    impl GetSample for Slow {
+        type Sample = usize;
+        fn get_sample(&self, index: usize) -> Self::Sample {
+            thread::sleep(Duration::from_millis(10 * (8 - index as u64)));
+            index
+        }
+    }
+    impl Dataset for Slow {}
+
+    /// Dataset whose sample 0 is loaded only once the sender of `gate` is dropped, or after 10 s so that a stalled
+    /// loader fails the test instead of hanging it.
+    struct Gated {
+        len: usize,
+        gate: Mutex<Receiver<()>>,
+    }
+    impl Gated {
+        fn new(len: usize) -> (Sender<()>, Self) {
+            let (release, gate) = mpsc::channel();
+            let gate = Mutex::new(gate);
+            (release, Self { len, gate })
+        }
+    }
+    impl Len for Gated {
+        fn len(&self) -> usize {
+            self.len
+        }
+    }
+    impl GetSample for Gated {
+        type Sample = usize;
+        fn get_sample(&self, index: usize) -> Self::Sample {
+            if index == 0 {
+                let _ = self
+                    .gate
+                    .lock()
+                    .unwrap()
+                    .recv_timeout(Duration::from_secs(10));
+            }
+            index
+        }
+    }
+    impl Dataset for Gated {}
+
+    #[test]
+    fn same_order_as_sequential() {
+        for num_workers in [1, 2, 5] {
+            let loader = DataLoader::builder((0..100).collect::<Vec<_>>())
+                .shuffle()
+                .batch_size(7)
+                .num_workers(num_workers)
+                .prefetch_factor(3)
+                .collate_fn(NoOpCollate)
+                .build();
+            let mut iter = loader.iter_prefetch();
+            assert_eq!(iter.len(), 15);
+            let batches: Vec<_> = iter.by_ref().collect();
+            assert_eq!(iter.len(), 0);
+            let mut samples: Vec<_> = batches.concat();
+            samples.sort_unstable();
+            assert_eq!(samples, (0..100).collect::<Vec<_>>());
+
+            let loader = DataLoader::builder((0..100).collect::<Vec<_>>())
+                .batch_size(7)
+                .num_workers(num_workers)
+                .collate_fn(NoOpCollate)
+                .build();
+            assert!(loader.iter_prefetch().eq(loader.iter()));
+        }
+    }
+
+    #[test]
+    fn in_order_slow_samples() {
+        let loader = DataLoader::builder(Slow)
+            .batch_size(2)
+            .num_workers(4)
+            .collate_fn(NoOpCollate)
+            .build();
+        assert_eq!(
+            loader.iter_prefetch().collect::<Vec<_>>(),
+            vec![vec![0, 1], vec![2, 3], vec![4, 5], vec![6, 7]]
+        );
+    }
+
+    #[test]
+    fn out_of_order() {
+        let (release, dataset) = Gated::new(8);
+        let loader = DataLoader::builder(dataset)
+            .batch_size(2)
+            .num_workers(4)
+            .in_order(false)
+            .collate_fn(NoOpCollate)
+            .build();
+        let mut iter = loader.iter_prefetch();
+        // Declared after the iterator, so that the gate is opened before the iterator joins the workers on a panic.
+        let release = release;
+        // The first batch is held back, the ones behind it aren't waiting for it.
+        let mut batches = Vec::new();
+        for _ in 0..3 {
+            let batch = iter.next().unwrap();
+            let batch_index = iter.batch_index().unwrap();
+            assert_ne!(batch_index, 0);
+            assert_eq!(u64::try_from(batch[0]).unwrap(), 2 * batch_index);
+            batches.push(batch);
+        }
+        // It's resumed from the first batch not yet yielded.
+        assert_eq!(iter.state_dict().num_yielded, 0);
+        drop(release);
+        assert_eq!(iter.next(), Some(vec![0, 1]));
+        assert_eq!(iter.batch_index(), Some(0));
+        assert_eq!(iter.state_dict().num_yielded, 4);
+        assert_eq!(iter.next(), None);
+        batches.sort_unstable();
+        assert_eq!(batches, vec![vec![2, 3], vec![4, 5], vec![6, 7]]);
+    }
+
+    #[test]
+    fn straggler_doesnt_stall() {
+        let (release, dataset) = Gated::new(20);
+        let loader = DataLoader::builder(dataset)
+            .num_workers(2)
+            .prefetch_factor(2)
+            .in_order(false)
+            .collate_fn(NoOpCollate)
+            .build();
+        let mut iter = loader.iter_prefetch();
+        let release = release;
+        // One worker is held on the first batch, the other one loads all the others.
+        let start = Instant::now();
+        let mut batches: Vec<_> = iter.by_ref().take(19).collect();
+        assert!(start.elapsed() < Duration::from_secs(5));
+        assert_ne!(iter.batch_index(), Some(0));
+        batches.sort_unstable();
+        assert_eq!(
+            batches,
+            (1..20).map(|index| vec![index]).collect::<Vec<_>>()
+        );
+        drop(release);
+        assert_eq!(iter.next(), Some(vec![0]));
+        assert_eq!(iter.next(), None);
+    }
+
+    #[test]
+    fn state_dict() {
+        let mut loader = DataLoader::builder((0..20).collect::<Vec<_>>())
+            .shuffle()
+            .seed(42)
+            .batch_size(3)
+            .num_workers(3)
+            .collate_fn(NoOpCollate)
+            .build();
+        let mut iter = loader.iter_prefetch();
+        iter.next();
+        let state = iter.state_dict();
+        assert_eq!(state.num_yielded, 1);
+        let remaining: Vec<_> = iter.collect();
+
+        loader.load_state_dict(state);
+        let resumed = loader.iter_prefetch();
+        assert_eq!(resumed.len(), 6);
+        assert_eq!(resumed.collect::<Vec<_>>(), remaining);
+    }
+
+    #[test]
+    fn early_drop() {
+        let loader = DataLoader::builder((0..1000).collect::<Vec<_>>())
+            .batch_size(2)
+            .num_workers(4)
+            .build();
+        let mut iter = loader.iter_prefetch();
+        iter.next();
+        drop(iter);
+    }
+
+    #[test]
+    fn drop_skips_queued_batches() {
+        /// Dataset taking 50 ms to load each sample.
+        struct VerySlow;
+        impl Len for VerySlow {
+            fn len(&self) -> usize {
+                100
+            }
+        }
+        impl GetSample for VerySlow {
+            type Sample = usize;
+            fn get_sample(&self, index: usize) -> Self::Sample {
+                thread::sleep(Duration::from_millis(50));
+                index
+            }
+        }
+        impl Dataset for VerySlow {}
+
+        let loader = DataLoader::builder(VerySlow)
+            .num_workers(2)
+            .prefetch_factor(50)
+            .collate_fn(NoOpCollate)
+            .build();
+        let mut iter = loader.iter_prefetch();
+        iter.next();
+        let start = Instant::now();
+        drop(iter);
+        // Loading the batches queued to the workers would take more than two seconds.
+        assert!(start.elapsed() < Duration::from_secs(1));
+    }
+
+    #[test]
+    #[should_panic(expected = "corrupted sample")]
+    fn worker_panic() {
+        let loader = DataLoader::builder(vec![1, 2, 3, 4])
+            .num_workers(2)
+            .collate_fn(|batch: Vec<i32>| {
+                assert!(batch[0] != 3, "corrupted sample");
+                batch
+            })
+            .build();
+        for _batch in loader.iter_prefetch() {}
+    }
+}
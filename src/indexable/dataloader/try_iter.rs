@@ -0,0 +1,600 @@
+//! Iterate over a fallible dataset, handling the samples that can't be loaded.
+
+use std::collections::VecDeque;
+use std::error::Error;
+use std::fmt;
+
+#[cfg(feature = "rayon")]
+use rayon::iter::{IntoParallelRefIterator, ParallelIterator};
+
+use super::{DataLoader, DataLoaderState};
+use crate::{
+    collate::{CollateError, DefaultCollate, TryCollate},
+    sampler::{BatchSampler, BatchSampling},
+    Dataset, TryGetSample,
+};
+
+/// How [`DataLoader::try_iter`] handles the samples that can't be loaded.
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd, Hash, Eq, Ord)]
+pub enum ErrorPolicy {
+    /// Yield the error of the first sample that can't be loaded, then stop the iteration.
+    Stop,
+    /// Skip the samples that can't be loaded, and refill the batch with the indices of the next batches.
+    ///
+    /// The batches keep their size, but there may be fewer batches than the batch sampler yields.
+    /// The skipped indices are given by [`TryDataLoaderIter::skipped_indices`].
+    Skip,
+    /// Try to load a sample up to this number of times again, then stop like [`ErrorPolicy::Stop`].
+    Retry(usize),
+}
+
+impl ErrorPolicy {
+    /// Number of times a sample that can't be loaded is tried again.
+    fn retries(self) -> usize {
+        match self {
+            Self::Retry(retries) => retries,
+            Self::Stop | Self::Skip => 0,
+        }
+    }
+}
+
+/// Error yielded by [`DataLoader::try_iter`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LoaderError<E> {
+    /// A sample can't be loaded.
+    Sample {
+        /// The index of the sample in the dataset.
+        index: usize,
+        /// The error returned by the dataset.
+        error: E,
+    },
+    /// The samples of a batch can't be collated.
+    Collate {
+        /// The indices in the dataset of the samples of the batch, the `batch_indices` of the error are positions in it.
+        indices: Vec<usize>,
+        /// The error returned by the collate function.
+        error: CollateError,
+    },
+}
+
+impl<E: fmt::Display> fmt::Display for LoaderError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Sample { index, error } => {
+                write!(f, "failed to load the sample {index}: {error}")
+            }
+            Self::Collate { indices, error } => {
+                write!(f, "{error}, in the batch of the samples {indices:?}")
+            }
+        }
+    }
+}
+
+impl<E: Error + 'static> Error for LoaderError<E> {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::Sample { error, .. } => Some(error),
+            Self::Collate { error, .. } => Some(error),
+        }
+    }
+}
+
+impl<D, B, C> DataLoader<D, B, C>
+where
+    D: Dataset + TryGetSample + Sync,
+    B: BatchSampling,
+    C: TryCollate<D::Output>,
+    D::Output: Send,
+    D::Error: Send,
+{
+    /// Return a not owning iterator over a fallible dataset, whose samples are a `Result`.
+    ///
+    /// The collate function gets the samples successfully loaded, and the errors are handled according to the
+    /// [`ErrorPolicy`] of the loader: the iterator yields `Result<Batch, LoaderError>`. A batch that can't be collated is
+    /// yielded as a [`LoaderError::Collate`], with the [`CollateError`] of the [`TryCollate`] function. The iteration
+    /// goes on with the next batch only with [`ErrorPolicy::Skip`].
+    ///
+    /// The samples are read with [`TryGetSample::try_get_batch`] if the dataset reads whole batches. The iteration
+    /// can be checkpointed with [`TryDataLoaderIter::state_dict`] and resumed with [`DataLoader::load_state_dict`].
+    ///
+    /// An infallible dataset can be iterated over with [`DatasetExt::map`](crate::indexable::DatasetExt::map),
+    /// wrapping its samples in `Ok`, to get the collate errors instead of a panic.
+    ///
+    /// ```
+    /// use ai_dataloader::collate::NoOpCollate;
+    /// use ai_dataloader::indexable::{DataLoader, ErrorPolicy, LoaderError};
+    ///
+    /// // The sample 2 is corrupted.
+    /// let dataset = vec![Ok(0), Ok(1), Err("corrupted"), Ok(3), Ok(4)];
+    /// let loader = DataLoader::builder(dataset.clone())
+    ///     .batch_size(2)
+    ///     .try_collate_fn(NoOpCollate)
+    ///     .build();
+    /// let mut iter = loader.try_iter();
+    /// assert_eq!(iter.next(), Some(Ok(vec![0, 1])));
+    /// assert!(matches!(iter.next(), Some(Err(LoaderError::Sample { index: 2, .. }))));
+    /// assert_eq!(iter.next(), None);
+    ///
+    /// let loader = DataLoader::builder(dataset)
+    ///     .batch_size(2)
+    ///     .error_policy(ErrorPolicy::Skip)
+    ///     .try_collate_fn(NoOpCollate)
+    ///     .build();
+    /// let batches: Result<Vec<_>, _> = loader.try_iter().collect();
+    /// assert_eq!(batches, Ok(vec![vec![0, 1], vec![3, 4]]));
+    /// ```
+    pub fn try_iter(&self) -> TryDataLoaderIter<'_, D, B, C> {
+        let (sampler_iter, seed, num_yielded) = self.start_iteration();
+        TryDataLoaderIter {
+            sampler_iter,
+            num_taken: num_yielded,
+            num_consumed: num_yielded,
+            epoch: self.epoch,
+            seed,
+            indices: VecDeque::new(),
+            batch_sizes: VecDeque::new(),
+            skipped_indices: Vec::new(),
+            stopped: false,
+            loader: self,
+        }
+    }
+}
+
+/// Iterate over a fallible dataset, it is returned by [`DataLoader::try_iter`].
+#[derive(Debug)]
+pub struct TryDataLoaderIter<'dataset, D, B = BatchSampler, C = DefaultCollate>
+where
+    B: BatchSampling,
+{
+    /// The batch iterator of this iterator.
+    sampler_iter: B::Iter,
+    /// Number of batches taken from the batch sampler, including the ones consumed before a checkpoint.
+    num_taken: u64,
+    /// Number of batches of the batch sampler whose samples have all been yielded or skipped.
+    num_consumed: u64,
+    /// The epoch of this iteration.
+    epoch: u64,
+    /// The seed of the random number generator given to the batch sampler.
+    seed: u64,
+    /// Indices taken from the batch sampler and not yet loaded, with the position of their batch in the batch sampler.
+    indices: VecDeque<(usize, u64)>,
+    /// Size of the batches taken from the batch sampler and not yet yielded.
+    batch_sizes: VecDeque<usize>,
+    /// Indices of the samples skipped because they couldn't be loaded.
+    skipped_indices: Vec<usize>,
+    /// Whether an error stopped the iteration.
+    stopped: bool,
+    /// The loader iterated over.
+    loader: &'dataset DataLoader<D, B, C>,
+}
+
+impl<D, B, C> TryDataLoaderIter<'_, D, B, C>
+where
+    D: Dataset + TryGetSample + Sync,
+    B: BatchSampling,
+    C: TryCollate<D::Output>,
+    D::Output: Send,
+    D::Error: Send,
+{
+    /// Indices of the samples skipped so far with [`ErrorPolicy::Skip`].
+    pub fn skipped_indices(&self) -> &[usize] {
+        &self.skipped_indices
+    }
+
+    /// Return the progress of this iteration, to resume it later with [`DataLoader::load_state_dict`].
+    ///
+    /// The state counts the batches of the batch sampler, not the batches yielded: with [`ErrorPolicy::Skip`],
+    /// a batch of the batch sampler partly used to refill a batch is loaded again by the resumed iteration.
+    /// After an error that stopped the iteration, the resumed iteration tries the failed batch again.
+    pub fn state_dict(&self) -> DataLoaderState {
+        DataLoaderState {
+            epoch: self.epoch,
+            seed: self.seed,
+            num_yielded: self.num_consumed,
+        }
+    }
+
+    /// Take the next batch of the batch sampler, return `false` if there are no more batches.
+    fn take_batch(&mut self) -> bool {
+        let Some(batch) = self.sampler_iter.next() else {
+            return false;
+        };
+        self.batch_sizes.push_back(batch.len());
+        let position = self.num_taken;
+        self.indices
+            .extend(batch.into_iter().map(|idx| (idx, position)));
+        self.num_taken += 1;
+        true
+    }
+
+    /// Load the samples at `indices`, with a single call to [`TryGetSample::try_get_batch`] if the dataset reads
+    /// whole batches, otherwise one by one, in parallel with the `rayon` feature.
+    fn load_all(&self, indices: &[usize]) -> Vec<Result<D::Output, D::Error>> {
+        let dataset = &*self.loader.dataset;
+        let retries = self.loader.error_policy.retries();
+        let load = || match dataset.try_get_batch(indices) {
+            Some(samples) => {
+                assert_eq!(
+                    samples.len(),
+                    indices.len(),
+                    "`try_get_batch` should return one result per index"
+                );
+                samples
+                    .into_iter()
+                    .zip(indices)
+                    .map(|(sample, idx)| retry(dataset, *idx, sample, retries))
+                    .collect()
+            }
+            None => load_each(dataset, indices, retries),
+        };
+        #[cfg(feature = "rayon")]
+        return self.loader.thread_pool.0.install(load);
+        #[cfg(not(feature = "rayon"))]
+        return load();
+    }
+}
+
+/// Load the samples at `indices` one by one, in parallel with the `rayon` feature.
+fn load_each<D>(dataset: &D, indices: &[usize], retries: usize) -> Vec<Result<D::Output, D::Error>>
+where
+    D: TryGetSample + Sync,
+    D::Output: Send,
+    D::Error: Send,
+{
+    #[cfg(feature = "rayon")]
+    return indices
+        .par_iter()
+        .map(|idx| retry(dataset, *idx, dataset.try_get_sample(*idx), retries))
+        .collect();
+    #[cfg(not(feature = "rayon"))]
+    return indices
+        .iter()
+        .map(|idx| retry(dataset, *idx, dataset.try_get_sample(*idx), retries))
+        .collect();
+}
+
+/// Try to load the sample at `index` again while it fails, up to `retries` times.
+fn retry<D: TryGetSample + ?Sized>(
+    dataset: &D,
+    index: usize,
+    mut sample: Result<D::Output, D::Error>,
+    retries: usize,
+) -> Result<D::Output, D::Error> {
+    for _ in 0..retries {
+        if sample.is_ok() {
+            break;
+        }
+        sample = dataset.try_get_sample(index);
+    }
+    sample
+}
+
+impl<D, B, C> Iterator for TryDataLoaderIter<'_, D, B, C>
+where
+    D: Dataset + TryGetSample + Sync,
+    B: BatchSampling,
+    C: TryCollate<D::Output>,
+    D::Output: Send,
+    D::Error: Send,
+{
+    type Item = Result<C::Output, LoaderError<D::Error>>;
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.stopped || (self.batch_sizes.is_empty() && !self.take_batch()) {
+            return None;
+        }
+        let batch_size = self
+            .batch_sizes
+            .pop_front()
+            .expect("a batch has been taken");
+        let mut samples = Vec::with_capacity(batch_size);
+        let mut loaded = Vec::with_capacity(batch_size);
+        // The indices of the next batches refill the batch when samples are skipped.
+        while samples.len() < batch_size && (!self.indices.is_empty() || self.take_batch()) {
+            let num_indices = (batch_size - samples.len()).min(self.indices.len());
+            let indices: Vec<usize> = self
+                .indices
+                .drain(..num_indices)
+                .map(|(idx, _)| idx)
+                .collect();
+            let skip = self.loader.error_policy == ErrorPolicy::Skip;
+            let batch = self.load_all(&indices);
+            for (index, sample) in indices.into_iter().zip(batch) {
+                match sample {
+                    Ok(sample) => {
+                        samples.push(sample);
+                        loaded.push(index);
+                    }
+                    Err(_) if skip => self.skipped_indices.push(index),
+                    Err(error) => {
+                        self.stopped = true;
+                        return Some(Err(LoaderError::Sample { index, error }));
+                    }
+                }
+            }
+        }
+        // All the remaining samples have been skipped.
+        if samples.is_empty() && batch_size > 0 {
+            self.num_consumed = self.num_taken;
+            return None;
+        }
+        let batch = self.loader.collate_fn.try_collate(samples);
+        if batch.is_err() && self.loader.error_policy != ErrorPolicy::Skip {
+            self.stopped = true;
+        } else {
+            // The batches whose indices have all been loaded are consumed.
+            self.num_consumed = self
+                .indices
+                .front()
+                .map_or(self.num_taken, |(_, position)| *position);
+        }
+        Some(batch.map_err(|error| LoaderError::Collate {
+            indices: loaded,
+            error,
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::collate::NoOpCollate;
+    use crate::{GetSample, Len};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// Dataset whose samples fail to load a given number of times.
+    struct Flaky {
+        failures: Vec<usize>,
+        attempts: Vec<AtomicUsize>,
+    }
+
+    impl Flaky {
+        fn new(failures: Vec<usize>) -> Self {
+            let attempts = failures.iter().map(|_| AtomicUsize::new(0)).collect();
+            Self { failures, attempts }
+        }
+    }
+
+    impl Len for Flaky {
+        fn len(&self) -> usize {
+            self.failures.len()
+        }
+    }
+
+    impl GetSample for Flaky {
+        type Sample = Result<usize, String>;
+        fn get_sample(&self, index: usize) -> Self::Sample {
+            let attempt = self.attempts[index].fetch_add(1, Ordering::Relaxed);
+            if attempt < self.failures[index] {
+                Err(format!("attempt {attempt}"))
+            } else {
+                Ok(index)
+            }
+        }
+    }
+
+    impl Dataset for Flaky {}
+
+    #[test]
+    fn stop() {
+        let loader = DataLoader::builder(Flaky::new(vec![0, 0, 0, 1, 0]))
+            .batch_size(2)
+            .try_collate_fn(NoOpCollate)
+            .build();
+        let batches: Vec<_> = loader.try_iter().collect();
+        let error = LoaderError::Sample {
+            index: 3,
+            error: String::from("attempt 0"),
+        };
+        assert_eq!(batches, vec![Ok(vec![0, 1]), Err(error.clone())]);
+        assert_eq!(error.to_string(), "failed to load the sample 3: attempt 0");
+    }
+
+    #[test]
+    fn skip() {
+        let loader = DataLoader::builder(Flaky::new(vec![0, 1, 1, 0, 0, 1, 0]))
+            .batch_size(2)
+            .error_policy(ErrorPolicy::Skip)
+            .try_collate_fn(NoOpCollate)
+            .build();
+        let mut iter = loader.try_iter();
+        assert_eq!(iter.next(), Some(Ok(vec![0, 3])));
+        assert_eq!(iter.next(), Some(Ok(vec![4, 6])));
+        assert_eq!(iter.next(), None);
+        assert_eq!(iter.skipped_indices(), [1, 2, 5]);
+    }
+
+    #[test]
+    fn skip_everything() {
+        let loader = DataLoader::builder(Flaky::new(vec![1; 5]))
+            .batch_size(2)
+            .error_policy(ErrorPolicy::Skip)
+            .try_collate_fn(NoOpCollate)
+            .build();
+        let mut iter = loader.try_iter();
+        assert_eq!(iter.next(), None);
+        assert_eq!(iter.skipped_indices().len(), 5);
+    }
+
+    #[test]
+    fn retry() {
+        let loader = DataLoader::builder(Flaky::new(vec![0, 2, 0, 3]))
+            .batch_size(2)
+            .error_policy(ErrorPolicy::Retry(2))
+            .try_collate_fn(NoOpCollate)
+            .build();
+        let batches: Vec<_> = loader.try_iter().collect();
+        assert_eq!(
+            batches,
+            vec![
+                Ok(vec![0, 1]),
+                Err(LoaderError::Sample {
+                    index: 3,
+                    error: String::from("attempt 2")
+                })
+            ]
+        );
+    }
+
+    #[test]
+    fn state_dict() {
+        let mut loader = DataLoader::builder(Flaky::new(vec![0, 1, 0, 0, 0, 0, 0]))
+            .batch_size(2)
+            .error_policy(ErrorPolicy::Skip)
+            .try_collate_fn(NoOpCollate)
+            .build();
+        let mut iter = loader.try_iter();
+        assert_eq!(iter.next(), Some(Ok(vec![0, 2])));
+        // The second batch of the batch sampler is partly used to refill the first one.
+        assert_eq!(iter.state_dict().num_yielded, 1);
+        assert_eq!(iter.next(), Some(Ok(vec![3, 4])));
+        let state = iter.state_dict();
+        assert_eq!(state.num_yielded, 2);
+        assert_eq!(iter.collect::<Vec<_>>(), vec![Ok(vec![5, 6])]);
+
+        // The sample 4 is loaded again with the rest of its batch.
+        loader.load_state_dict(state);
+        assert_eq!(
+            loader.try_iter().collect::<Vec<_>>(),
+            vec![Ok(vec![4, 5]), Ok(vec![6])]
+        );
+    }
+
+    #[test]
+    fn resume_after_stop() {
+        let mut loader = DataLoader::builder(Flaky::new(vec![0, 0, 1, 0]))
+            .batch_size(2)
+            .try_collate_fn(NoOpCollate)
+            .build();
+        let mut iter = loader.try_iter();
+        assert_eq!(iter.next(), Some(Ok(vec![0, 1])));
+        assert!(iter.next().unwrap().is_err());
+        let state = iter.state_dict();
+        assert_eq!(state.num_yielded, 1);
+
+        // The failed batch is tried again.
+        loader.load_state_dict(state);
+        assert_eq!(loader.try_iter().collect::<Vec<_>>(), vec![Ok(vec![2, 3])]);
+    }
+
+    #[test]
+    fn try_get_batch() {
+        /// Fallible dataset reading whole batches at once, it counts the reads.
+        struct Batched {
+            num_reads: AtomicUsize,
+        }
+        impl Len for Batched {
+            fn len(&self) -> usize {
+                5
+            }
+        }
+        impl GetSample for Batched {
+            type Sample = Result<usize, String>;
+            fn get_sample(&self, _index: usize) -> Self::Sample {
+                unreachable!("the samples are read by batch")
+            }
+            fn get_batch(&self, indices: &[usize]) -> Option<Vec<Self::Sample>> {
+                self.num_reads.fetch_add(1, Ordering::Relaxed);
+                let samples = indices
+                    .iter()
+                    .map(|idx| {
+                        if *idx == 1 {
+                            Err(String::from("corrupted"))
+                        } else {
+                            Ok(*idx)
+                        }
+                    })
+                    .collect();
+                Some(samples)
+            }
+        }
+        impl Dataset for Batched {}
+
+        let loader = DataLoader::builder(Batched {
+            num_reads: AtomicUsize::new(0),
+        })
+        .batch_size(2)
+        .error_policy(ErrorPolicy::Skip)
+        .try_collate_fn(NoOpCollate)
+        .build();
+        assert_eq!(
+            loader.try_iter().collect::<Vec<_>>(),
+            vec![Ok(vec![0, 2]), Ok(vec![3, 4])]
+        );
+        // One read per batch, and one per refill.
+        assert_eq!(loader.dataset.num_reads.load(Ordering::Relaxed), 4);
+    }
+
+    #[test]
+    #[should_panic(expected = "`try_get_batch` should return one result per index")]
+    fn try_get_batch_missing_sample() {
+        /// Fallible dataset dropping the last sample of each batch.
+        struct Truncated;
+        impl Len for Truncated {
+            fn len(&self) -> usize {
+                4
+            }
+        }
+        impl GetSample for Truncated {
+            type Sample = Result<usize, String>;
+            fn get_sample(&self, index: usize) -> Self::Sample {
+                Ok(index)
+            }
+            fn get_batch(&self, indices: &[usize]) -> Option<Vec<Self::Sample>> {
+                Some(indices[1..].iter().map(|idx| Ok(*idx)).collect())
+            }
+        }
+        impl Dataset for Truncated {}
+
+        let loader = DataLoader::builder(Truncated)
+            .batch_size(2)
+            .try_collate_fn(NoOpCollate)
+            .build();
+        loader.try_iter().for_each(drop);
+    }
+
+    #[test]
+    fn default_collate() {
+        let loader = DataLoader::builder(vec![Ok(1), Err(()), Ok(3)])
+            .batch_size(2)
+            .error_policy(ErrorPolicy::Skip)
+            .build();
+        assert_eq!(
+            loader.try_iter().collect::<Vec<_>>(),
+            vec![Ok(ndarray::array![1, 3])]
+        );
+    }
+
+    #[test]
+    fn collate_error() {
+        let dataset: Vec<Result<_, String>> = vec![
+            Ok(vec![0, 1]),
+            Ok(vec![2, 3]),
+            Ok(vec![4]),
+            Ok(vec![5, 6]),
+            Ok(vec![7, 8]),
+        ];
+        let loader = DataLoader::builder(dataset.clone()).batch_size(2).build();
+        let batches: Vec<_> = loader.try_iter().collect();
+        assert_eq!(batches.len(), 2);
+        let Err(LoaderError::Collate { indices, error }) = &batches[1] else {
+            panic!("the second batch should fail to collate");
+        };
+        assert_eq!(indices, &[2, 3]);
+        assert_eq!(error.batch_indices, [1]);
+        assert_eq!(
+            batches[1].as_ref().unwrap_err().to_string(),
+            "failed to collate the samples: the samples [1] have the lengths [2], \
+            different from the length 1 of the sample 0, in the batch of the samples [2, 3]"
+        );
+
+        // With `Skip`, the iteration goes on after the batch that can't be collated.
+        let loader = DataLoader::builder(dataset)
+            .batch_size(2)
+            .error_policy(ErrorPolicy::Skip)
+            .build();
+        let batches: Vec<_> = loader.try_iter().collect();
+        assert_eq!(batches.len(), 3);
+        assert!(batches[1].is_err());
+        assert_eq!(batches[2], Ok(vec![ndarray::array![7], ndarray::array![8]]));
+    }
+}
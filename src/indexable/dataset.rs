@@ -1,11 +1,14 @@
 use std::collections::VecDeque;
+use std::sync::Arc;
 
 mod len;
 pub use len::Len;
 mod ndarray_dataset;
-pub use ndarray_dataset::NdarrayDataset;
+pub use ndarray_dataset::{NdarrayDataset, Ndarrays};
 mod get_sample;
-pub use get_sample::GetSample;
+pub use get_sample::{GetSample, GetSampleRef, TryGetSample};
+mod dataset_ext;
+pub use dataset_ext::{ConcatDataset, DatasetExt, Map, Subset};
 
 /// A dataset is just something that has a length and is indexable.
 /// A `Vec` of `dataset` collate output must also be collatable.
@@ -34,7 +37,17 @@ pub use get_sample::GetSample;
 /// }
 /// ```
 /// And we want to return a tuple (label, text) when indexing, it will no be possible with `std:ops::Index`.
-pub trait Dataset: Len + GetSample {}
+pub trait Dataset: Len + GetSample {
+    /// Set the current epoch, it is called by [`DataLoader::set_epoch`](crate::indexable::DataLoader::set_epoch).
+    ///
+    /// A dataset applying random augmentations can seed them from the epoch and the index of the sample,
+    /// so that they change from one epoch to another while staying reproducible. By default it does nothing.
+    ///
+    /// It takes `&self` so that it reaches the datasets shared behind an [`Arc`], like the splits of
+    /// [`DatasetExt::random_split`]: the epoch is stored with interior mutability, for instance in an
+    /// [`AtomicU64`](std::sync::atomic::AtomicU64).
+    fn set_epoch(&self, _epoch: u64) {}
+}
 
 /// Dataset could become something like that when functor trait will be available.
 #[doc(hidden)]
@@ -48,3 +61,15 @@ where
 
 impl<T> Dataset for Vec<T> where T: Clone {}
 impl<T> Dataset for VecDeque<T> where T: Clone {}
+
+impl<T: Dataset + ?Sized> Dataset for Box<T> {
+    fn set_epoch(&self, epoch: u64) {
+        (**self).set_epoch(epoch);
+    }
+}
+
+impl<T: Dataset + ?Sized> Dataset for Arc<T> {
+    fn set_epoch(&self, epoch: u64) {
+        (**self).set_epoch(epoch);
+    }
+}
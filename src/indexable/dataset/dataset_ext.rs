@@ -0,0 +1,517 @@
+//! Adapters to transform and combine datasets without writing a new dataset type.
+
+use std::fmt;
+use std::sync::Arc;
+
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
+
+use super::{Dataset, GetSample, GetSampleRef};
+use crate::Len;
+
+/// Adapters available on every [`Dataset`].
+///
+/// The adapters are datasets themselves, so they can be nested and given to the
+/// [`DataLoader`](crate::indexable::DataLoader).
+///
+/// ```
+/// use ai_dataloader::indexable::{DataLoader, DatasetExt};
+///
+/// let dataset = vec![1, 2, 3, 4, 5, 6].map(|sample| sample * 10);
+/// let [train, val] = <[_; 2]>::try_from(dataset.random_split(&[0.5, 0.5], 42)).unwrap();
+/// let loader = DataLoader::builder(train).batch_size(3).build();
+/// assert_eq!(loader.iter().count(), 1);
+/// ```
+pub trait DatasetExt: Dataset + Sized {
+    /// Lazily transform each sample with `f`, when it is fetched.
+    #[must_use]
+    fn map<F, T>(self, f: F) -> Map<Self, F>
+    where
+        F: Fn(Self::Sample) -> T,
+    {
+        Map { dataset: self, f }
+    }
+
+    /// View the dataset through a list of indices.
+    ///
+    /// # Panics
+    ///
+    /// Panics if an index is out of the dataset.
+    #[must_use]
+    fn subset(self, indices: Vec<usize>) -> Subset<Self> {
+        Subset::new(self, indices)
+    }
+
+    /// Randomly split the dataset into non-overlapping subsets, for instance train, validation and test sets.
+    ///
+    /// Each subset gets `fraction * len` samples, rounded down, then the remaining samples are given one by one
+    /// to the first subsets. The split only depends on `seed`. The subsets share the dataset.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a fraction is negative, or if the fractions don't sum to one.
+    #[must_use]
+    fn random_split(self, fractions: &[f64], seed: u64) -> Vec<Subset<Arc<Self>>> {
+        assert!(
+            fractions
+                .iter()
+                .all(|fraction| fraction.is_finite() && *fraction >= 0.),
+            "fractions should be finite and non-negative"
+        );
+        assert!(
+            (fractions.iter().sum::<f64>() - 1.).abs() < 1e-6,
+            "fractions should sum to one"
+        );
+        let len = self.len();
+        #[allow(
+            clippy::cast_precision_loss,
+            clippy::cast_possible_truncation,
+            clippy::cast_sign_loss
+        )]
+        let mut lengths: Vec<usize> = fractions
+            .iter()
+            .map(|fraction| (fraction * len as f64).floor() as usize)
+            .collect();
+        let total: usize = lengths.iter().sum();
+        // The fractions may sum to slightly more than one, the excess is taken off the largest split.
+        if total > len {
+            let largest = (0..lengths.len())
+                .max_by_key(|split| lengths[*split])
+                .expect("the lengths exceed a length of at least one");
+            lengths[largest] -= total - len;
+        }
+        let remainder = len.saturating_sub(total);
+        let num_splits = lengths.len();
+        for split in 0..remainder {
+            lengths[split % num_splits] += 1;
+        }
+
+        let mut indices: Vec<usize> = (0..len).collect();
+        indices.shuffle(&mut StdRng::seed_from_u64(seed));
+        let dataset = Arc::new(self);
+        let mut start = 0;
+        lengths
+            .into_iter()
+            .map(|length| {
+                let subset = Subset {
+                    dataset: Arc::clone(&dataset),
+                    indices: indices[start..start + length].to_vec(),
+                };
+                start += length;
+                subset
+            })
+            .collect()
+    }
+}
+
+impl<D: Dataset> DatasetExt for D {}
+
+/// Dataset transforming the samples of another one. See [`DatasetExt::map`].
+#[derive(Clone)]
+pub struct Map<D, F> {
+    /// The base dataset.
+    dataset: D,
+    /// Transformation applied to each sample.
+    f: F,
+}
+
+impl<D: fmt::Debug, F> fmt::Debug for Map<D, F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Map")
+            .field("dataset", &self.dataset)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<D: Len, F> Len for Map<D, F> {
+    fn len(&self) -> usize {
+        self.dataset.len()
+    }
+}
+
+impl<D, F, T> GetSample for Map<D, F>
+where
+    D: GetSample,
+    F: Fn(D::Sample) -> T,
+{
+    type Sample = T;
+    fn get_sample(&self, index: usize) -> Self::Sample {
+        (self.f)(self.dataset.get_sample(index))
+    }
+    fn get_batch(&self, indices: &[usize]) -> Option<Vec<Self::Sample>> {
+        let samples = self.dataset.get_batch(indices)?;
+        Some(samples.into_iter().map(&self.f).collect())
+    }
+}
+
+impl<D, F, T> Dataset for Map<D, F>
+where
+    D: Dataset,
+    F: Fn(D::Sample) -> T,
+{
+    fn set_epoch(&self, epoch: u64) {
+        self.dataset.set_epoch(epoch);
+    }
+}
+
+/// View of a dataset through a list of indices, the sample `i` of the subset is the sample `indices[i]` of the dataset.
+#[derive(Debug, Clone, PartialEq, PartialOrd, Hash, Eq, Ord)]
+pub struct Subset<D> {
+    /// The base dataset.
+    dataset: D,
+    /// The indices of the base dataset in the subset.
+    indices: Vec<usize>,
+}
+
+impl<D: Len> Subset<D> {
+    /// Create a new [`Subset`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if an index is out of the dataset.
+    #[must_use]
+    pub fn new(dataset: D, indices: Vec<usize>) -> Self {
+        if let Some(idx) = indices.iter().find(|idx| **idx >= dataset.len()) {
+            panic!(
+                "the index {idx} is out of a dataset of length {}",
+                dataset.len()
+            );
+        }
+        Self { dataset, indices }
+    }
+
+    /// The base dataset.
+    #[must_use]
+    pub fn dataset(&self) -> &D {
+        &self.dataset
+    }
+
+    /// The indices of the base dataset in the subset.
+    #[must_use]
+    pub fn indices(&self) -> &[usize] {
+        &self.indices
+    }
+}
+
+impl<D> Len for Subset<D> {
+    fn len(&self) -> usize {
+        self.indices.len()
+    }
+}
+
+impl<D: GetSample> GetSample for Subset<D> {
+    type Sample = D::Sample;
+    fn get_sample(&self, index: usize) -> Self::Sample {
+        self.dataset.get_sample(self.indices[index])
+    }
+    fn get_batch(&self, indices: &[usize]) -> Option<Vec<Self::Sample>> {
+        let indices: Vec<usize> = indices.iter().map(|idx| self.indices[*idx]).collect();
+        self.dataset.get_batch(&indices)
+    }
+}
+
+impl<D: GetSampleRef> GetSampleRef for Subset<D> {
+    type SampleRef<'a>
+        = D::SampleRef<'a>
+    where
+        Self: 'a;
+    fn get_sample_ref(&self, index: usize) -> Self::SampleRef<'_> {
+        self.dataset.get_sample_ref(self.indices[index])
+    }
+}
+
+impl<D: Dataset> Dataset for Subset<D> {
+    fn set_epoch(&self, epoch: u64) {
+        self.dataset.set_epoch(epoch);
+    }
+}
+
+/// Dataset joining several datasets with the same sample type, one after the other.
+///
+/// To join datasets of different types, box them: `ConcatDataset<Box<dyn Dataset<Sample = T> + Sync>>`.
+///
+/// ```
+/// use ai_dataloader::indexable::ConcatDataset;
+/// use ai_dataloader::{GetSample, Len};
+///
+/// let dataset = ConcatDataset::new(vec![vec![1, 2], vec![3], vec![4, 5, 6]]);
+/// assert_eq!(dataset.len(), 6);
+/// assert_eq!(dataset.get_sample(2), 3);
+/// ```
+#[derive(Debug, Clone, PartialEq, PartialOrd, Hash, Eq, Ord)]
+pub struct ConcatDataset<D> {
+    /// The joined datasets.
+    datasets: Vec<D>,
+    /// The total length of the datasets up to each one, included.
+    cumulative_lengths: Vec<usize>,
+}
+
+impl<D: Len> ConcatDataset<D> {
+    /// Create a new [`ConcatDataset`].
+    #[must_use]
+    pub fn new(datasets: Vec<D>) -> Self {
+        let cumulative_lengths = datasets
+            .iter()
+            .scan(0, |total, dataset| {
+                *total += dataset.len();
+                Some(*total)
+            })
+            .collect();
+        Self {
+            datasets,
+            cumulative_lengths,
+        }
+    }
+
+    /// The joined datasets.
+    #[must_use]
+    pub fn datasets(&self) -> &[D] {
+        &self.datasets
+    }
+}
+
+impl<D> Len for ConcatDataset<D> {
+    fn len(&self) -> usize {
+        self.cumulative_lengths.last().copied().unwrap_or(0)
+    }
+}
+
+impl<D> ConcatDataset<D> {
+    /// The dataset containing `index`, and the index in this dataset.
+    fn locate(&self, index: usize) -> (&D, usize) {
+        let (dataset_idx, index) = self.locate_idx(index);
+        (&self.datasets[dataset_idx], index)
+    }
+
+    /// The position of the dataset containing `index`, and the index in this dataset.
+    fn locate_idx(&self, index: usize) -> (usize, usize) {
+        let dataset_idx = self
+            .cumulative_lengths
+            .partition_point(|total| *total <= index);
+        let offset = if dataset_idx == 0 {
+            0
+        } else {
+            self.cumulative_lengths[dataset_idx - 1]
+        };
+        (dataset_idx, index - offset)
+    }
+}
+
+impl<D: GetSample> GetSample for ConcatDataset<D> {
+    type Sample = D::Sample;
+    fn get_sample(&self, index: usize) -> Self::Sample {
+        let (dataset, index) = self.locate(index);
+        dataset.get_sample(index)
+    }
+    fn get_batch(&self, indices: &[usize]) -> Option<Vec<Self::Sample>> {
+        // Each dataset reads its indices at once, then the samples are put back in the order of `indices`.
+        let mut positions = vec![Vec::new(); self.datasets.len()];
+        let mut local_indices = vec![Vec::new(); self.datasets.len()];
+        for (position, index) in indices.iter().enumerate() {
+            let (dataset_idx, index) = self.locate_idx(*index);
+            positions[dataset_idx].push(position);
+            local_indices[dataset_idx].push(index);
+        }
+        let mut samples: Vec<Option<D::Sample>> = indices.iter().map(|_| None).collect();
+        for (dataset_idx, local_indices) in local_indices.iter().enumerate() {
+            if local_indices.is_empty() {
+                continue;
+            }
+            let batch = self.datasets[dataset_idx].get_batch(local_indices)?;
+            assert_eq!(
+                batch.len(),
+                local_indices.len(),
+                "`get_batch` should return one sample per index"
+            );
+            for (position, sample) in positions[dataset_idx].iter().zip(batch) {
+                samples[*position] = Some(sample);
+            }
+        }
+        samples.into_iter().collect()
+    }
+}
+
+impl<D: GetSampleRef> GetSampleRef for ConcatDataset<D> {
+    type SampleRef<'a>
+        = D::SampleRef<'a>
+    where
+        Self: 'a;
+    fn get_sample_ref(&self, index: usize) -> Self::SampleRef<'_> {
+        let (dataset, index) = self.locate(index);
+        dataset.get_sample_ref(index)
+    }
+}
+
+impl<D: Dataset> Dataset for ConcatDataset<D> {
+    fn set_epoch(&self, epoch: u64) {
+        for dataset in &self.datasets {
+            dataset.set_epoch(epoch);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use itertools::Itertools;
+    use std::sync::atomic::{AtomicU64, Ordering};
+
+    #[test]
+    fn map() {
+        let dataset = vec![1, 2, 3].map(|sample| sample.to_string());
+        assert_eq!(dataset.len(), 3);
+        assert_eq!(dataset.get_sample(1), "2");
+    }
+
+    #[test]
+    fn subset() {
+        let dataset = vec!['a', 'b', 'c', 'd'].subset(vec![3, 1]);
+        assert_eq!(dataset.len(), 2);
+        assert_eq!(dataset.get_sample(0), 'd');
+        assert_eq!(dataset.get_sample(1), 'b');
+    }
+
+    #[test]
+    fn get_batch() {
+        /// Dataset reading whole batches at once, its samples are their index.
+        struct Batched;
+        impl Len for Batched {
+            fn len(&self) -> usize {
+                4
+            }
+        }
+        impl GetSample for Batched {
+            type Sample = usize;
+            fn get_sample(&self, _index: usize) -> Self::Sample {
+                unreachable!("the samples are read by batch")
+            }
+            fn get_batch(&self, indices: &[usize]) -> Option<Vec<Self::Sample>> {
+                Some(indices.to_vec())
+            }
+        }
+        impl Dataset for Batched {}
+
+        let dataset = Batched.subset(vec![3, 1, 0]).map(|sample| sample * 10);
+        assert_eq!(dataset.get_batch(&[0, 2]), Some(vec![30, 0]));
+        assert_eq!(vec![1, 2].map(|sample| sample * 10).get_batch(&[0]), None);
+
+        let dataset = ConcatDataset::new(vec![Batched, Batched]);
+        assert_eq!(dataset.get_batch(&[5, 1, 6]), Some(vec![1, 1, 2]));
+        assert_eq!(ConcatDataset::new(vec![vec![1, 2]]).get_batch(&[1]), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "the index 4 is out of a dataset of length 4")]
+    fn subset_out_of_bounds() {
+        let _dataset = vec!['a', 'b', 'c', 'd'].subset(vec![4]);
+    }
+
+    #[test]
+    fn concat() {
+        let dataset = ConcatDataset::new(vec![vec![1, 2], vec![], vec![3, 4, 5]]);
+        assert_eq!(dataset.len(), 5);
+        assert_eq!(
+            (0..5)
+                .map(|idx| dataset.get_sample(idx))
+                .collect::<Vec<_>>(),
+            vec![1, 2, 3, 4, 5]
+        );
+        assert_eq!(ConcatDataset::<Vec<i32>>::new(Vec::new()).len(), 0);
+    }
+
+    #[test]
+    fn borrowed() {
+        let dataset = ConcatDataset::new(vec![
+            vec![String::from("a")].subset(vec![0]),
+            vec![String::from("b"), String::from("c")].subset(vec![1]),
+        ]);
+        assert_eq!(dataset.get_sample_ref(1), "c");
+    }
+
+    #[test]
+    fn concat_different_types() {
+        let datasets: Vec<Box<dyn Dataset<Sample = i32> + Sync>> = vec![
+            Box::new(vec![1, 2]),
+            Box::new(vec![3, 4].map(|sample| sample * 10)),
+        ];
+        let dataset = ConcatDataset::new(datasets);
+        assert_eq!(dataset.get_sample(3), 40);
+    }
+
+    #[test]
+    fn random_split() {
+        let splits = (0..10)
+            .collect::<Vec<_>>()
+            .random_split(&[0.7, 0.2, 0.1], 42);
+        assert_eq!(
+            splits.iter().map(Len::len).collect::<Vec<_>>(),
+            vec![7, 2, 1]
+        );
+        let samples: Vec<_> = splits
+            .iter()
+            .flat_map(|split| (0..split.len()).map(|idx| split.get_sample(idx)))
+            .collect();
+        assert!(samples.iter().all_unique());
+        assert_eq!(samples.len(), 10);
+
+        // The split only depends on the seed.
+        let same_splits = (0..10)
+            .collect::<Vec<_>>()
+            .random_split(&[0.7, 0.2, 0.1], 42);
+        assert_eq!(splits[0].indices(), same_splits[0].indices());
+    }
+
+    #[test]
+    fn random_split_set_epoch() {
+        /// Dataset recording the last epoch it was given.
+        struct Epoch(AtomicU64);
+        impl Len for Epoch {
+            fn len(&self) -> usize {
+                4
+            }
+        }
+        impl GetSample for Epoch {
+            type Sample = u64;
+            fn get_sample(&self, _index: usize) -> Self::Sample {
+                self.0.load(Ordering::Relaxed)
+            }
+        }
+        impl Dataset for Epoch {
+            fn set_epoch(&self, epoch: u64) {
+                self.0.store(epoch, Ordering::Relaxed);
+            }
+        }
+
+        let splits = Epoch(AtomicU64::new(0)).random_split(&[0.5, 0.5], 0);
+        splits[0].set_epoch(3);
+        assert_eq!(splits[0].get_sample(0), 3);
+        // The splits share the dataset.
+        assert_eq!(splits[1].get_sample(0), 3);
+    }
+
+    #[test]
+    fn random_split_remainder() {
+        let splits = vec![0; 10].random_split(&[0.25, 0.25, 0.5], 0);
+        assert_eq!(
+            splits.iter().map(Len::len).collect::<Vec<_>>(),
+            vec![3, 2, 5]
+        );
+    }
+
+    #[test]
+    fn random_split_rounding_excess() {
+        // The fractions sum to slightly more than one, within the tolerance.
+        let splits = vec![(); 2_000_000].random_split(&[0.500_000_9, 0.5], 0);
+        assert_eq!(
+            splits.iter().map(Len::len).collect::<Vec<_>>(),
+            vec![1_000_000, 1_000_000]
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "fractions should sum to one")]
+    fn random_split_invalid_fractions() {
+        let _splits = vec![0; 10].random_split(&[0.5, 0.4], 0);
+    }
+}
@@ -1,4 +1,5 @@
 use std::collections::VecDeque;
+use std::sync::Arc;
 
 /// Return a sample from the dataset at a given index.
 pub trait GetSample {
@@ -6,6 +7,18 @@ pub trait GetSample {
     type Sample: Sized;
     /// Return the dataset sample corresponding to the index.
     fn get_sample(&self, index: usize) -> Self::Sample;
+    /// Return the dataset samples corresponding to a batch of indices, in the same order, or `None` if the dataset
+    /// doesn't read whole batches.
+    ///
+    /// The returned `Vec` must hold exactly one sample per index, the loader panics otherwise.
+    ///
+    /// Override it for backends that are faster when reading a whole batch at once, such as a single database query
+    /// or a coalesced disk read. The loader calls it once per batch, from its thread pool with the `rayon` feature.
+    /// By default it returns `None`, and the loader calls [`GetSample::get_sample`] on each index instead,
+    /// in parallel with the `rayon` feature.
+    fn get_batch(&self, _indices: &[usize]) -> Option<Vec<Self::Sample>> {
+        None
+    }
 }
 
 impl<T: Clone> GetSample for Vec<T> {
@@ -22,4 +35,119 @@ impl<T: Clone> GetSample for VecDeque<T> {
     }
 }
 
+impl<T: GetSample + ?Sized> GetSample for Box<T> {
+    type Sample = T::Sample;
+    fn get_sample(&self, index: usize) -> Self::Sample {
+        (**self).get_sample(index)
+    }
+    fn get_batch(&self, indices: &[usize]) -> Option<Vec<Self::Sample>> {
+        (**self).get_batch(indices)
+    }
+}
+
+impl<T: GetSample + ?Sized> GetSample for Arc<T> {
+    type Sample = T::Sample;
+    fn get_sample(&self, index: usize) -> Self::Sample {
+        (**self).get_sample(index)
+    }
+    fn get_batch(&self, indices: &[usize]) -> Option<Vec<Self::Sample>> {
+        (**self).get_batch(indices)
+    }
+}
+
+/// Return a sample from the dataset at a given index, or the error that prevented from loading it,
+/// for instance a corrupted file.
+///
+/// It's implemented for every [`GetSample`] whose samples are a `Result`: a fallible dataset is a dataset of `Result`.
+/// Its errors are handled by [`DataLoader::try_iter`](crate::indexable::DataLoader::try_iter) according to
+/// an [`ErrorPolicy`](crate::indexable::ErrorPolicy).
+pub trait TryGetSample {
+    /// Type of one sample of the dataset, when it's loaded successfully.
+    type Output;
+    /// Type of the error when a sample can't be loaded.
+    type Error;
+    /// Return the dataset sample corresponding to the index.
+    ///
+    /// # Errors
+    ///
+    /// Return the error that prevented from loading the sample.
+    fn try_get_sample(&self, index: usize) -> Result<Self::Output, Self::Error>;
+    /// Return the dataset samples corresponding to a batch of indices, in the same order, or `None` if the dataset
+    /// doesn't read whole batches. It's the fallible counterpart of [`GetSample::get_batch`].
+    ///
+    /// The returned `Vec` must hold exactly one result per index, the loader panics otherwise.
+    fn try_get_batch(&self, _indices: &[usize]) -> Option<Vec<Result<Self::Output, Self::Error>>> {
+        None
+    }
+}
+
+impl<D, T, E> TryGetSample for D
+where
+    D: GetSample<Sample = Result<T, E>> + ?Sized,
+{
+    type Output = T;
+    type Error = E;
+    fn try_get_sample(&self, index: usize) -> Result<Self::Output, Self::Error> {
+        self.get_sample(index)
+    }
+    fn try_get_batch(&self, indices: &[usize]) -> Option<Vec<Result<Self::Output, Self::Error>>> {
+        self.get_batch(indices)
+    }
+}
+
+/// Borrow a sample from the dataset at a given index, without copying it.
+///
+/// It's the zero-copy counterpart of [`GetSample`], used by [`DataLoader::iter_ref`](crate::indexable::DataLoader::iter_ref).
+/// The sample can borrow from the dataset, for instance a reference to an element of a `Vec`, or an `ArrayView`
+/// into a row of an [`NdarrayDataset`](crate::indexable::NdarrayDataset), that the collate function copies only once,
+/// straight into the batch.
+pub trait GetSampleRef {
+    /// Type of one borrowed sample of the dataset.
+    type SampleRef<'a>
+    where
+        Self: 'a;
+    /// Return the dataset sample corresponding to the index, borrowed from the dataset.
+    fn get_sample_ref(&self, index: usize) -> Self::SampleRef<'_>;
+}
+
+impl<T> GetSampleRef for Vec<T> {
+    type SampleRef<'a>
+        = &'a T
+    where
+        T: 'a;
+    fn get_sample_ref(&self, index: usize) -> Self::SampleRef<'_> {
+        &self[index]
+    }
+}
+
+impl<T> GetSampleRef for VecDeque<T> {
+    type SampleRef<'a>
+        = &'a T
+    where
+        T: 'a;
+    fn get_sample_ref(&self, index: usize) -> Self::SampleRef<'_> {
+        &self[index]
+    }
+}
+
+impl<T: GetSampleRef + ?Sized> GetSampleRef for Box<T> {
+    type SampleRef<'a>
+        = T::SampleRef<'a>
+    where
+        Self: 'a;
+    fn get_sample_ref(&self, index: usize) -> Self::SampleRef<'_> {
+        (**self).get_sample_ref(index)
+    }
+}
+
+impl<T: GetSampleRef + ?Sized> GetSampleRef for Arc<T> {
+    type SampleRef<'a>
+        = T::SampleRef<'a>
+    where
+        Self: 'a;
+    fn get_sample_ref(&self, index: usize) -> Self::SampleRef<'_> {
+        (**self).get_sample_ref(index)
+    }
+}
+
 // TODO: `GetSample` for Array?
@@ -1,4 +1,5 @@
 use std::collections::{BTreeMap, BTreeSet, BinaryHeap, HashMap, HashSet, LinkedList, VecDeque};
+use std::sync::Arc;
 
 /// Basic trait for anything that could have a length.
 /// Even if a lot of struct have a `len()` method in the standard library,
@@ -62,3 +63,15 @@ impl<T> Len for BinaryHeap<T> {
         self.len()
     }
 }
+
+impl<T: Len + ?Sized> Len for Box<T> {
+    fn len(&self) -> usize {
+        (**self).len()
+    }
+}
+
+impl<T: Len + ?Sized> Len for Arc<T> {
+    fn len(&self) -> usize {
+        (**self).len()
+    }
+}
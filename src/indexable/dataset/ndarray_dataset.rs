@@ -1,82 +1,252 @@
-use super::{Dataset, GetSample};
+use super::{Dataset, GetSample, GetSampleRef};
 use crate::Len;
-use ndarray::{Array, Axis, Dimension, RemoveAxis};
+use itertools::multizip;
+use ndarray::{Array, ArrayBase, ArrayView, Axis, Data, Dimension, RemoveAxis};
 
-/// Basic dataset than can contains two `ndarray` of any dimension.
-#[derive(Debug, PartialEq, Hash, Eq)]
-pub struct NdarrayDataset<A1, A2, D1, D2>
-where
-    A1: Clone,
-    A2: Clone,
-    D1: Dimension + RemoveAxis,
-    D2: Dimension + RemoveAxis,
-{
+/// Basic dataset made of one or several `ndarray` of any dimension and type, indexed along their first axis.
+///
+/// It's the equivalent of the `TensorDataset` of `PyTorch`. The sample `i` is the tuple of the sub-arrays at the
+/// index `i` of each array. The arrays can be owned, shared (`ArcArray`) or borrowed (`ArrayView`),
+/// so the data doesn't have to be copied into the dataset. With [`GetSampleRef`], the samples are views into the
+/// arrays, that the collate function copies straight into the batch.
+///
+/// ```
+/// use ai_dataloader::indexable::NdarrayDataset;
+/// use ai_dataloader::{GetSample, Len};
+/// use ndarray::{array, arr0};
+///
+/// let images = array![[1., 2.], [3., 4.], [5., 6.]];
+/// let labels = array![0, 1, 0];
+/// let dataset = NdarrayDataset::new((images.view(), labels));
+/// assert_eq!(dataset.len(), 3);
+/// assert_eq!(dataset.get_sample(1), (array![3., 4.], arr0(1)));
+/// ```
+#[derive(Debug, Clone, PartialEq, Hash, Eq)]
+pub struct NdarrayDataset<T> {
     /// The content of the dataset.
-    pub ndarrays: (Array<A1, D1>, Array<A2, D2>),
-}
-impl<A1, A2, D1, D2> Dataset for NdarrayDataset<A1, A2, D1, D2>
-where
-    A1: Clone,
-    A2: Clone,
-    D1: Dimension + RemoveAxis,
-    D2: Dimension + RemoveAxis,
-{
+    ndarrays: T,
+    /// The common length of the arrays along their first axis.
+    len: usize,
 }
 
-impl<A1, A2, D1, D2> Clone for NdarrayDataset<A1, A2, D1, D2>
-where
-    A1: Clone,
-    A2: Clone,
-    D1: Dimension + RemoveAxis,
-    D2: Dimension + RemoveAxis,
-{
-    fn clone(&self) -> Self {
+impl<T: Ndarrays> NdarrayDataset<T> {
+    /// Create a new [`NdarrayDataset`] from a tuple of arrays.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the arrays don't have the same length along their first axis.
+    #[must_use]
+    pub fn new(ndarrays: T) -> Self {
+        let lengths = ndarrays.lengths();
+        assert!(
+            lengths.iter().all(|len| *len == lengths[0]),
+            "the arrays should have the same length along their first axis, got {lengths:?}"
+        );
         Self {
-            ndarrays: self.ndarrays.clone(),
+            len: lengths[0],
+            ndarrays,
         }
     }
+
+    /// The arrays of the dataset.
+    #[must_use]
+    pub fn ndarrays(&self) -> &T {
+        &self.ndarrays
+    }
+
+    /// Consume the dataset, returning its arrays.
+    #[must_use]
+    pub fn into_ndarrays(self) -> T {
+        self.ndarrays
+    }
 }
 
-impl<A1, A2, D1, D2> Len for NdarrayDataset<A1, A2, D1, D2>
-where
-    A1: Clone,
-    A2: Clone,
-    D1: Dimension + RemoveAxis,
-    D2: Dimension + RemoveAxis,
-{
+impl<T: Ndarrays> Dataset for NdarrayDataset<T> {}
+
+impl<T> Len for NdarrayDataset<T> {
     fn len(&self) -> usize {
-        self.ndarrays.0.shape()[0]
+        self.len
     }
 }
-impl<A1, A2, D1, D2> GetSample for NdarrayDataset<A1, A2, D1, D2>
-where
-    A1: Clone,
-    A2: Clone,
-    D1: Dimension + RemoveAxis,
-    D2: Dimension + RemoveAxis,
-{
-    type Sample = (
-        Array<A1, <D1 as Dimension>::Smaller>,
-        Array<A2, <D2 as Dimension>::Smaller>,
-    );
+
+impl<T: Ndarrays> GetSample for NdarrayDataset<T> {
+    type Sample = T::Sample;
     fn get_sample(&self, index: usize) -> Self::Sample {
-        (
-            self.ndarrays.0.index_axis(Axis(0), index).into_owned(),
-            self.ndarrays.1.index_axis(Axis(0), index).into_owned(),
-        )
+        self.ndarrays.get_sample(index)
+    }
+    fn get_batch(&self, indices: &[usize]) -> Option<Vec<Self::Sample>> {
+        Some(self.ndarrays.get_batch(indices))
+    }
+}
+
+impl<T: Ndarrays> GetSampleRef for NdarrayDataset<T> {
+    type SampleRef<'a>
+        = T::SampleRef<'a>
+    where
+        T: 'a;
+    fn get_sample_ref(&self, index: usize) -> Self::SampleRef<'_> {
+        self.ndarrays.get_sample_ref(index)
     }
 }
 
+/// Tuple of arrays that can be put into a [`NdarrayDataset`], implemented for tuples of 1 up to 12
+/// arrays of at least one dimension.
+pub trait Ndarrays {
+    /// The tuple of sub-arrays at one index.
+    type Sample;
+    /// The tuple of views of the sub-arrays at one index.
+    type SampleRef<'a>
+    where
+        Self: 'a;
+    /// The length of each array along its first axis.
+    fn lengths(&self) -> Vec<usize>;
+    /// The tuple of sub-arrays at `index` along the first axis, copied into owned arrays.
+    fn get_sample(&self, index: usize) -> Self::Sample;
+    /// The tuples of sub-arrays at `indices`, gathering the rows of each array with a single `select`.
+    fn get_batch(&self, indices: &[usize]) -> Vec<Self::Sample>;
+    /// The tuple of views of the sub-arrays at `index` along the first axis.
+    fn get_sample_ref(&self, index: usize) -> Self::SampleRef<'_>;
+}
+
+/// Split an array into its rows along the first axis, moving the elements into the rows instead of copying them.
+fn into_rows<A, D>(array: Array<A, D>) -> impl Iterator<Item = Array<A, D::Smaller>>
+where
+    D: Dimension + RemoveAxis,
+{
+    let row_dim = array.raw_dim().remove_axis(Axis(0));
+    let row_len = row_dim.size();
+    let num_rows = array.len_of(Axis(0));
+    // The elements are iterated over in the logical order, row after row.
+    let mut elements = array.into_iter();
+    (0..num_rows).map(move |_| {
+        Array::from_shape_vec(row_dim.clone(), elements.by_ref().take(row_len).collect())
+            .expect("a row has the shape of the array without its first axis")
+    })
+}
+
+/// `Ndarrays` implementation for tuples, up to 12 elements like the `DefaultCollate` of tuples.
+macro_rules! ndarrays_impl {
+    ($($storage:ident $dim:ident $idx:tt)+) => {
+        impl<$($storage, $dim),+> Ndarrays for ($(ArrayBase<$storage, $dim>,)+)
+        where
+            $(
+                $storage: Data,
+                $storage::Elem: Clone,
+                $dim: Dimension + RemoveAxis,
+            )+
+        {
+            type Sample = ($(Array<$storage::Elem, $dim::Smaller>,)+);
+            type SampleRef<'a> = ($(ArrayView<'a, $storage::Elem, $dim::Smaller>,)+) where Self: 'a;
+
+            fn lengths(&self) -> Vec<usize> {
+                vec![$(self.$idx.len_of(Axis(0)),)+]
+            }
+
+            fn get_sample(&self, index: usize) -> Self::Sample {
+                ($(self.$idx.index_axis(Axis(0), index).to_owned(),)+)
+            }
+
+            fn get_sample_ref(&self, index: usize) -> Self::SampleRef<'_> {
+                ($(self.$idx.index_axis(Axis(0), index),)+)
+            }
+
+            fn get_batch(&self, indices: &[usize]) -> Vec<Self::Sample> {
+                let selected = ($(self.$idx.select(Axis(0), indices),)+);
+                multizip(($(into_rows(selected.$idx),)+)).collect()
+            }
+        }
+    };
+}
+
+ndarrays_impl! { S0 D0 0 }
+ndarrays_impl! { S0 D0 0 S1 D1 1 }
+ndarrays_impl! { S0 D0 0 S1 D1 1 S2 D2 2 }
+ndarrays_impl! { S0 D0 0 S1 D1 1 S2 D2 2 S3 D3 3 }
+ndarrays_impl! { S0 D0 0 S1 D1 1 S2 D2 2 S3 D3 3 S4 D4 4 }
+ndarrays_impl! { S0 D0 0 S1 D1 1 S2 D2 2 S3 D3 3 S4 D4 4 S5 D5 5 }
+ndarrays_impl! { S0 D0 0 S1 D1 1 S2 D2 2 S3 D3 3 S4 D4 4 S5 D5 5 S6 D6 6 }
+ndarrays_impl! { S0 D0 0 S1 D1 1 S2 D2 2 S3 D3 3 S4 D4 4 S5 D5 5 S6 D6 6 S7 D7 7 }
+ndarrays_impl! { S0 D0 0 S1 D1 1 S2 D2 2 S3 D3 3 S4 D4 4 S5 D5 5 S6 D6 6 S7 D7 7 S8 D8 8 }
+ndarrays_impl! { S0 D0 0 S1 D1 1 S2 D2 2 S3 D3 3 S4 D4 4 S5 D5 5 S6 D6 6 S7 D7 7 S8 D8 8 S9 D9 9 }
+ndarrays_impl! { S0 D0 0 S1 D1 1 S2 D2 2 S3 D3 3 S4 D4 4 S5 D5 5 S6 D6 6 S7 D7 7 S8 D8 8 S9 D9 9 S10 D10 10 }
+ndarrays_impl! { S0 D0 0 S1 D1 1 S2 D2 2 S3 D3 3 S4 D4 4 S5 D5 5 S6 D6 6 S7 D7 7 S8 D8 8 S9 D9 9 S10 D10 10 S11 D11 11 }
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use ndarray::{arr0, array};
+    use ndarray::{arr0, array, ArcArray};
+
     #[test]
     fn ndarray_dataset() {
-        let dataset = NdarrayDataset {
-            ndarrays: (array![1, 2], array![3, 4]),
-        };
+        let dataset = NdarrayDataset::new((array![1, 2], array![3, 4]));
         assert_eq!(dataset.get_sample(0), (arr0(1), arr0(3)));
         assert_eq!(dataset.get_sample(1), (arr0(2), arr0(4)));
     }
+
+    #[test]
+    fn single_array() {
+        let dataset = NdarrayDataset::new((array![[1, 2], [3, 4], [5, 6]],));
+        assert_eq!(dataset.len(), 3);
+        assert_eq!(dataset.get_sample(2), (array![5, 6],));
+    }
+
+    #[test]
+    fn several_arrays() {
+        let dataset = NdarrayDataset::new((
+            array![[[1., 2.]], [[3., 4.]]],
+            array![0, 1],
+            array![["a"], ["b"]],
+        ));
+        assert_eq!(dataset.len(), 2);
+        assert_eq!(
+            dataset.get_sample(1),
+            (array![[3., 4.]], arr0(1), array!["b"])
+        );
+    }
+
+    #[test]
+    fn shared_and_borrowed() {
+        let data = array![[1, 2], [3, 4]];
+        let labels: ArcArray<i32, _> = array![5, 6].into_shared();
+        let dataset = NdarrayDataset::new((data.view(), labels.clone()));
+        assert_eq!(dataset.get_sample(0), (array![1, 2], arr0(5)));
+        assert_eq!(dataset.ndarrays().1, labels);
+    }
+
+    #[test]
+    fn get_batch() {
+        let dataset = NdarrayDataset::new((array![[1, 2], [3, 4], [5, 6]], array![7, 8, 9]));
+        assert_eq!(
+            dataset.get_batch(&[2, 0, 2]),
+            Some(vec![
+                (array![5, 6], arr0(9)),
+                (array![1, 2], arr0(7)),
+                (array![5, 6], arr0(9))
+            ])
+        );
+        let dataset = NdarrayDataset::new((Array::from_shape_fn((4, 2, 3), |(i, j, k)| {
+            100 * i + 10 * j + k
+        }),));
+        let batch = dataset.get_batch(&[3, 1]).unwrap();
+        assert_eq!(batch[0].0, dataset.get_sample(3).0);
+        assert_eq!(batch[1].0, dataset.get_sample(1).0);
+        assert!(dataset.get_batch(&[]).unwrap().is_empty());
+    }
+
+    #[test]
+    fn borrowed() {
+        let dataset = NdarrayDataset::new((array![[1, 2], [3, 4]], array![5, 6]));
+        assert_eq!(
+            dataset.get_sample_ref(1),
+            (array![3, 4].view(), arr0(6).view())
+        );
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "the arrays should have the same length along their first axis, got [2, 3]"
+    )]
+    fn different_lengths() {
+        let _dataset = NdarrayDataset::new((array![1, 2], array![3, 4, 5]));
+    }
 }
@@ -1,10 +1,10 @@
 use crate::{
     collate::{Collate, DefaultCollate},
-    Dataset,
+    Dataset, GetSample, GetSampleRef,
 };
 
 #[cfg(feature = "rayon")]
-use crate::THREAD_POOL;
+use rayon::ThreadPool;
 
 #[cfg(feature = "rayon")]
 use rayon::iter::ParallelIterator;
@@ -37,9 +37,12 @@ where
     pub(crate) dataset: &'dataset D,
     /// The function (generic struct) used to collate data together.
     pub(crate) collate_fn: &'dataset C,
+    /// The thread pool of the loader.
+    #[cfg(feature = "rayon")]
+    pub(crate) thread_pool: &'dataset ThreadPool,
 }
 
-impl<'dataset, D, C> Fetcher<D, C> for MapDatasetFetcher<'dataset, D, C>
+impl<D, C> Fetcher<D, C> for MapDatasetFetcher<'_, D, C>
 where
     D: Dataset + Sync,
     C: Collate<D::Sample>,
@@ -49,19 +52,72 @@ where
         // As the batch length can vary depending on if the last element is dropped or not, we can't use a fix len array to
         // collect the data.
         #[cfg(feature = "rayon")]
-        let data = THREAD_POOL
-            .get()
-            .expect("thread pool is initialized")
-            .install(|| {
-                possibly_batched_index
-                    .into_par_iter()
-                    .map(|idx| self.dataset.get_sample(idx))
-                    .collect()
-            });
+        let data =
+            self.thread_pool
+                .install(|| match self.dataset.get_batch(&possibly_batched_index) {
+                    Some(samples) => check_batch_len(samples, &possibly_batched_index),
+                    None => possibly_batched_index
+                        .into_par_iter()
+                        .map(|idx| self.dataset.get_sample(idx))
+                        .collect(),
+                });
+        #[cfg(not(feature = "rayon"))]
+        let data = get_batch(self.dataset, &possibly_batched_index);
+
+        self.collate_fn.collate(data)
+    }
+}
+
+/// Return the samples of a batch, with a single call to [`GetSample::get_batch`] if the dataset reads whole batches.
+pub(crate) fn get_batch<D: GetSample + ?Sized>(dataset: &D, indices: &[usize]) -> Vec<D::Sample> {
+    match dataset.get_batch(indices) {
+        Some(samples) => check_batch_len(samples, indices),
+        None => indices.iter().map(|idx| dataset.get_sample(*idx)).collect(),
+    }
+}
+
+/// Check that [`GetSample::get_batch`] returned one sample per index.
+fn check_batch_len<T>(samples: Vec<T>, indices: &[usize]) -> Vec<T> {
+    assert_eq!(
+        samples.len(),
+        indices.len(),
+        "`get_batch` should return one sample per index"
+    );
+    samples
+}
+
+/// Fetcher for map-style dataset borrowing its samples. The collate function gets the borrowed samples.
+#[derive(Debug)]
+pub(crate) struct MapDatasetRefFetcher<'dataset, D, C = DefaultCollate> {
+    /// The dataset data will be fetch from.
+    pub(crate) dataset: &'dataset D,
+    /// The function (generic struct) used to collate data together.
+    pub(crate) collate_fn: &'dataset C,
+    /// The thread pool of the loader.
+    #[cfg(feature = "rayon")]
+    pub(crate) thread_pool: &'dataset ThreadPool,
+}
+
+impl<'dataset, D, C> MapDatasetRefFetcher<'dataset, D, C>
+where
+    D: GetSampleRef + Sync,
+    C: Collate<D::SampleRef<'dataset>>,
+    D::SampleRef<'dataset>: Send,
+{
+    /// Given a batch of index, return the result of the collate function on the borrowed samples.
+    pub(crate) fn fetch(&self, possibly_batched_index: Vec<usize>) -> C::Output {
+        let dataset = self.dataset;
+        #[cfg(feature = "rayon")]
+        let data = self.thread_pool.install(|| {
+            possibly_batched_index
+                .into_par_iter()
+                .map(|idx| dataset.get_sample_ref(idx))
+                .collect()
+        });
         #[cfg(not(feature = "rayon"))]
         let data = possibly_batched_index
             .into_iter()
-            .map(|idx| self.dataset.get_sample(idx))
+            .map(|idx| dataset.get_sample_ref(idx))
             .collect();
 
         self.collate_fn.collate(data)
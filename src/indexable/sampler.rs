@@ -1,19 +1,134 @@
 //! Defines the strategy to draw samples from the dataset.
 //!
 
-use crate::Len;
+use rand::rngs::StdRng;
+
+use crate::{rng_from_seed, Len};
 
 mod batch_sampler;
+mod block_shuffle_sampler;
+mod bucket_batch_sampler;
+mod class_balanced_batch_sampler;
+mod distributed_sampler;
+mod lazy_random_sampler;
+mod precomputed_batches;
 mod random_sampler;
+mod sampler_ext;
 mod sequential_sampler;
+mod subset_random_sampler;
+mod subset_sequential_sampler;
+mod token_budget_batch_sampler;
+mod weighted_random_sampler;
 
 pub use batch_sampler::{BatchIterator, BatchSampler};
-pub use random_sampler::RandomSampler;
+pub use block_shuffle_sampler::{BlockShuffleSampler, BlockShuffleSamplerIter};
+pub use bucket_batch_sampler::BucketBatchSampler;
+pub use class_balanced_batch_sampler::ClassBalancedBatchSampler;
+pub use distributed_sampler::DistributedSampler;
+pub use lazy_random_sampler::{LazyRandomSampler, LazyRandomSamplerIter};
+pub use precomputed_batches::PrecomputedBatches;
+pub use random_sampler::{RandomSampler, RandomSamplerIter};
+pub use sampler_ext::{Chain, Filter, Interleave, Repeat, RepeatIter, SamplerExt, Take};
 pub use sequential_sampler::SequentialSampler;
+pub use subset_random_sampler::SubsetRandomSampler;
+pub use subset_sequential_sampler::SubsetSequentialSampler;
+pub use token_budget_batch_sampler::TokenBudgetBatchSampler;
+pub use weighted_random_sampler::{WeightedRandomSampler, WeightedRandomSamplerIter};
+
+/// Every Sampler has a length and yields indices of the dataset.
+///
+/// A sampler is iterated once per epoch through [`Sampler::iter_with_rng`], so it can own any state
+/// it needs (a list of indices, weights, a configuration...). A ready-made sampler is given to the
+/// [`DataLoader`](crate::indexable::DataLoader) with the builder's `with_sampler` method.
+///
+/// ```
+/// use ai_dataloader::indexable::DataLoader;
+/// use ai_dataloader::sampler::Sampler;
+/// use ai_dataloader::Len;
+/// use rand::rngs::StdRng;
+///
+/// /// Yield the indices in reverse order.
+/// struct ReverseSampler {
+///     data_source_len: usize,
+/// }
+///
+/// impl Len for ReverseSampler {
+///     fn len(&self) -> usize {
+///         self.data_source_len
+///     }
+/// }
+///
+/// impl Sampler for ReverseSampler {
+///     type Iter = std::iter::Rev<std::ops::Range<usize>>;
+///     fn iter_with_rng(&self, _rng: &mut StdRng) -> Self::Iter {
+///         (0..self.data_source_len).rev()
+///     }
+/// }
+///
+/// let loader = DataLoader::builder(vec![1, 2, 3])
+///     .with_sampler(ReverseSampler { data_source_len: 3 })
+///     .build();
+/// let mut iter = loader.iter();
+/// assert_eq!(iter.next(), Some(ndarray::array![3]));
+/// ```
+pub trait Sampler: Len {
+    /// The iterator over the indices of one epoch.
+    type Iter: Iterator<Item = usize>;
+
+    /// Iterate over the sampler, drawing all the randomness from `rng`.
+    ///
+    /// The [`DataLoader`](crate::indexable::DataLoader) hands its own generator to the sampler, that way
+    /// seeding the loader makes the order of the indices reproducible.
+    /// Samplers that don't need randomness can ignore `rng`.
+    fn iter_with_rng(&self, rng: &mut StdRng) -> Self::Iter;
+
+    /// Iterate over the sampler, with a random number generator seeded from the system entropy.
+    fn iter(&self) -> Self::Iter {
+        self.iter_with_rng(&mut rng_from_seed(None, 0))
+    }
+
+    /// Set the current epoch, for samplers whose order depends on it such as the [`DistributedSampler`].
+    /// By default it does nothing.
+    fn set_epoch(&mut self, _epoch: u64) {}
+}
+
+/// A [`Sampler`] that can be created from the length of the dataset alone.
+///
+/// Such samplers can be set on the `DataLoader` builder with `sampler::<S>()`.
+pub trait FromLen: Sampler {
+    /// Create a new sampler from the dataset length.
+    fn from_len(data_source_len: usize) -> Self;
+}
+
+/// Every batch sampler has a length, its number of batches, and yields batches of indices.
+///
+/// [`BatchSampler`] gathers the indices of a [`Sampler`] into batches of a fixed size, but any strategy can be used
+/// by implementing this trait: the batches can have any size or makeup. A list of batches computed beforehand,
+/// [`PrecomputedBatches`], is the simplest batch sampler.
+///
+/// ```
+/// use ai_dataloader::indexable::DataLoader;
+/// use ai_dataloader::sampler::PrecomputedBatches;
+///
+/// let loader = DataLoader::builder(vec![1, 2, 3, 4, 5])
+///     .with_batch_sampler(PrecomputedBatches(vec![vec![0, 1, 2], vec![3, 4]]))
+///     .build();
+/// let mut iter = loader.iter();
+/// assert_eq!(iter.next(), Some(ndarray::array![1, 2, 3]));
+/// assert_eq!(iter.next(), Some(ndarray::array![4, 5]));
+/// ```
+pub trait BatchSampling: Len {
+    /// The iterator over the batches of one epoch.
+    type Iter: Iterator<Item = Vec<usize>>;
+
+    /// Iterate over the batches, drawing all the randomness from `rng`.
+    fn iter_with_rng(&self, rng: &mut StdRng) -> Self::Iter;
+
+    /// Iterate over the batches, with a random number generator seeded from the system entropy.
+    fn iter(&self) -> Self::Iter {
+        self.iter_with_rng(&mut rng_from_seed(None, 0))
+    }
 
-// TODO: maybe copy is too restrictive and should be replaced by Clone?
-/// Every Sampler is iterable and has a length.
-pub trait Sampler: Len + IntoIterator<Item = usize> + Copy {
-    /// Create a new sampler form the dataset length.
-    fn new(data_source_len: usize) -> Self;
+    /// Set the current epoch, for batch samplers whose order depends on it. By default it does nothing.
+    fn set_epoch(&mut self, _epoch: u64) {}
 }
@@ -1,4 +1,6 @@
-use super::{Sampler, SequentialSampler};
+use rand::rngs::StdRng;
+
+use super::{BatchSampling, Sampler, SequentialSampler};
 use crate::Len;
 
 /// Wraps another sampler to yield a mini-batch of indices.
@@ -42,26 +44,46 @@ impl<S: Sampler> Len for BatchSampler<S> {
     ///
     /// If `drop_last` is set to false, even an incomplete batch will be counted.
     fn len(&self) -> usize {
-        if self.drop_last {
-            self.sampler.len() / self.batch_size
-        } else {
-            (self.sampler.len() + self.batch_size - 1) / self.batch_size
-        }
+        num_batches(self.sampler.len(), self.batch_size, self.drop_last)
     }
 }
 impl<S: Sampler> BatchSampler<S> {
     /// Return an iterator over the [`BatchSampler`].
-    pub fn iter(&self) -> BatchIterator<S::IntoIter> {
+    pub fn iter(&self) -> BatchIterator<S::Iter> {
+        BatchIterator {
+            sampler: self.sampler.iter(),
+            batch_size: self.batch_size,
+            drop_last: self.drop_last,
+        }
+    }
+
+    /// Set the current epoch of the base sampler.
+    pub fn set_epoch(&mut self, epoch: u64) {
+        self.sampler.set_epoch(epoch);
+    }
+
+    /// Return an iterator over the [`BatchSampler`], the base sampler drawing its randomness from `rng`.
+    pub fn iter_with_rng(&self, rng: &mut StdRng) -> BatchIterator<S::Iter> {
         BatchIterator {
-            sampler: self.sampler.into_iter(),
+            sampler: self.sampler.iter_with_rng(rng),
             batch_size: self.batch_size,
             drop_last: self.drop_last,
         }
     }
 }
 
+impl<S: Sampler> BatchSampling for BatchSampler<S> {
+    type Iter = BatchIterator<S::Iter>;
+    fn iter_with_rng(&self, rng: &mut StdRng) -> Self::Iter {
+        BatchSampler::iter_with_rng(self, rng)
+    }
+    fn set_epoch(&mut self, epoch: u64) {
+        BatchSampler::set_epoch(self, epoch);
+    }
+}
+
 impl<S: Sampler> IntoIterator for &BatchSampler<S> {
-    type IntoIter = BatchIterator<<S as IntoIterator>::IntoIter>;
+    type IntoIter = BatchIterator<S::Iter>;
     type Item = Vec<usize>;
     fn into_iter(self) -> Self::IntoIter {
         self.iter()
@@ -106,13 +128,21 @@ where
         None
     }
     fn size_hint(&self) -> (usize, Option<usize>) {
-        let (lower, _) = self.sampler.size_hint();
-        let lower = if self.drop_last {
-            lower / self.batch_size
-        } else {
-            (lower + self.batch_size - 1) / self.batch_size
-        };
-        (lower, Some(lower))
+        let (lower, upper) = self.sampler.size_hint();
+        (
+            num_batches(lower, self.batch_size, self.drop_last),
+            upper.map(|upper| num_batches(upper, self.batch_size, self.drop_last)),
+        )
+    }
+}
+
+/// Number of batch of `batch_size` indices among `num_indices`, without overflowing for infinite samplers.
+#[allow(clippy::fn_params_excessive_bools)]
+fn num_batches(num_indices: usize, batch_size: usize, drop_last: bool) -> usize {
+    if drop_last {
+        num_indices / batch_size
+    } else {
+        num_indices / batch_size + usize::from(num_indices % batch_size != 0)
     }
 }
 
@@ -121,6 +151,7 @@ impl<I> ExactSizeIterator for BatchIterator<I> where I: Iterator<Item = usize> +
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::sampler::PrecomputedBatches;
 
     #[test]
     fn basics() {
@@ -142,12 +173,18 @@ mod tests {
     }
     #[test]
     fn batch_sampler() {
-        // TODO : test from pytorch, need to support custom batch sampler
+        // Test from pytorch, with a custom batch sampler.
         let mut batches = Vec::new();
         for i in (0..20).step_by(5) {
-            batches.push([i..i + 2]);
-            batches.push([i + 2..i + 5]);
+            batches.push((i..i + 2).collect::<Vec<_>>());
+            batches.push((i + 2..i + 5).collect::<Vec<_>>());
         }
+        assert_eq!(batches.len(), 8);
+        let mut iter = PrecomputedBatches(batches).iter();
+        assert_eq!(iter.next(), Some(vec![0, 1]));
+        assert_eq!(iter.next(), Some(vec![2, 3, 4]));
+        assert_eq!(iter.next(), Some(vec![5, 6]));
+        assert_eq!(iter.count(), 5);
     }
     #[test]
     fn len() {
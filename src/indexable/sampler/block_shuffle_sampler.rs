@@ -0,0 +1,188 @@
+//! Shuffle contiguous blocks of indices, for datasets read from disk.
+
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
+
+use super::{Len, Sampler};
+
+/// Sampler that yields the indices in a near-random order while keeping the reads mostly sequential.
+///
+/// The indices are split into contiguous blocks of `block_size` indices, and the order of the blocks is shuffled.
+/// Then the blocks are taken by windows of `window` blocks, and the indices are shuffled inside each window.
+/// The larger the window, the closer to a full shuffle, at the cost of less sequential reads.
+///
+/// It's meant for datasets that live on disk, where fully random accesses thrash the page cache.
+///
+/// ```
+/// use ai_dataloader::sampler::{BlockShuffleSampler, Sampler};
+///
+/// // Blocks of 4 indices, shuffled by windows of 2 blocks.
+/// let sampler = BlockShuffleSampler::new(16, 4).window(2);
+/// let indices: Vec<_> = sampler.iter().collect();
+/// for window in indices.chunks(8) {
+///     // A window only contains the indices of 2 blocks.
+///     let mut blocks: Vec<_> = window.iter().map(|idx| idx / 4).collect();
+///     blocks.sort_unstable();
+///     blocks.dedup();
+///     assert_eq!(blocks.len(), 2);
+/// }
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd, Hash, Eq, Ord)]
+pub struct BlockShuffleSampler {
+    /// The length of the dataset that will be sampled.
+    data_source_len: usize,
+    /// Number of contiguous indices in a block.
+    block_size: usize,
+    /// Number of blocks whose indices are shuffled together.
+    window: usize,
+}
+
+impl BlockShuffleSampler {
+    /// Create a new [`BlockShuffleSampler`], with blocks of `block_size` indices shuffled one by one.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `block_size` is zero.
+    #[must_use]
+    pub fn new(data_source_len: usize, block_size: usize) -> Self {
+        assert!(block_size > 0, "block_size should be positive");
+        Self {
+            data_source_len,
+            block_size,
+            window: 1,
+        }
+    }
+
+    /// Shuffle the indices of `window` consecutive blocks together, instead of one block at a time.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `window` is zero.
+    #[must_use]
+    pub fn window(mut self, window: usize) -> Self {
+        assert!(window > 0, "window should be positive");
+        self.window = window;
+        self
+    }
+}
+
+impl Len for BlockShuffleSampler {
+    fn len(&self) -> usize {
+        self.data_source_len
+    }
+}
+
+impl Sampler for BlockShuffleSampler {
+    type Iter = BlockShuffleSamplerIter;
+    fn iter_with_rng(&self, rng: &mut StdRng) -> Self::Iter {
+        let num_blocks = self.data_source_len / self.block_size
+            + usize::from(self.data_source_len % self.block_size != 0);
+        let mut blocks: Vec<usize> = (0..num_blocks).collect();
+        blocks.shuffle(rng);
+        BlockShuffleSamplerIter {
+            sampler: *self,
+            blocks: blocks.into_iter(),
+            window: Vec::new().into_iter(),
+            rng: StdRng::from_rng(rng),
+            remaining: self.data_source_len,
+        }
+    }
+}
+
+/// Iterator that returns the indices of a [`BlockShuffleSampler`].
+///
+/// Only the order of the blocks and the indices of the current window are kept in memory.
+#[derive(Debug)]
+pub struct BlockShuffleSamplerIter {
+    /// The sampler configuration.
+    sampler: BlockShuffleSampler,
+    /// The blocks not yet yielded, in shuffled order.
+    blocks: std::vec::IntoIter<usize>,
+    /// The shuffled indices of the current window.
+    window: std::vec::IntoIter<usize>,
+    /// Random number generator used to shuffle the windows.
+    rng: StdRng,
+    /// Number of indices not yet yielded.
+    remaining: usize,
+}
+
+impl Iterator for BlockShuffleSamplerIter {
+    type Item = usize;
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(idx) = self.window.next() {
+            self.remaining -= 1;
+            return Some(idx);
+        }
+        let block_size = self.sampler.block_size;
+        let data_source_len = self.sampler.data_source_len;
+        let mut window: Vec<usize> = self
+            .blocks
+            .by_ref()
+            .take(self.sampler.window)
+            .flat_map(|block| block * block_size..((block + 1) * block_size).min(data_source_len))
+            .collect();
+        window.shuffle(&mut self.rng);
+        self.window = window.into_iter();
+        let idx = self.window.next();
+        if idx.is_some() {
+            self.remaining -= 1;
+        }
+        idx
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl ExactSizeIterator for BlockShuffleSamplerIter {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn permutation() {
+        for (len, block_size, window) in
+            [(0, 3, 1), (10, 3, 1), (10, 3, 2), (100, 7, 4), (5, 10, 3)]
+        {
+            let sampler = BlockShuffleSampler::new(len, block_size).window(window);
+            let mut iter = sampler.iter();
+            assert_eq!(iter.len(), len);
+            let mut indices: Vec<_> = iter.by_ref().collect();
+            assert_eq!(iter.len(), 0);
+            indices.sort_unstable();
+            assert_eq!(indices, (0..len).collect::<Vec<_>>());
+        }
+    }
+
+    #[test]
+    fn blocks_stay_together() {
+        let sampler = BlockShuffleSampler::new(100, 10);
+        let indices: Vec<_> = sampler.iter().collect();
+        assert_ne!(indices, (0..100).collect::<Vec<_>>());
+        for block in indices.chunks(10) {
+            assert!(block.iter().all(|idx| idx / 10 == block[0] / 10));
+        }
+    }
+
+    #[test]
+    fn seeded() {
+        let sampler = BlockShuffleSampler::new(100, 8).window(3);
+        assert_eq!(
+            sampler
+                .iter_with_rng(&mut StdRng::seed_from_u64(42))
+                .collect::<Vec<_>>(),
+            sampler
+                .iter_with_rng(&mut StdRng::seed_from_u64(42))
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "block_size should be positive")]
+    fn zero_block_size() {
+        let _sampler = BlockShuffleSampler::new(10, 0);
+    }
+}
@@ -0,0 +1,215 @@
+//! Gather samples of similar length into the same batches.
+
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+
+use super::{BatchSampling, Len};
+use crate::Dataset;
+
+/// Batch sampler that groups the indices into buckets of similar length, and draws each batch from a single bucket.
+///
+/// It is used for variable length sequences (text, audio, ...) so that the padding of a batch doesn't waste compute.
+/// A sample of length `l` goes into the bucket `i` such that `bucket_boundaries[i - 1] <= l < bucket_boundaries[i]`,
+/// so there is one more bucket than boundaries.
+///
+/// By default the indices are shuffled inside each bucket, and the batches are shuffled across the buckets.
+///
+/// ```
+/// use ai_dataloader::sampler::{BatchSampling, BucketBatchSampler};
+/// use ai_dataloader::Len;
+///
+/// let lengths = vec![3, 12, 5, 15, 4, 11];
+/// let sampler = BucketBatchSampler::new(&lengths, &[10], 2);
+/// assert_eq!(sampler.len(), 4);
+/// for batch in sampler.iter() {
+///     // A batch only contains short or long samples.
+///     assert!(batch.iter().all(|&idx| lengths[idx] < 10) || batch.iter().all(|&idx| lengths[idx] >= 10));
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, PartialOrd, Hash, Eq, Ord)]
+pub struct BucketBatchSampler {
+    /// The indices of the dataset, grouped by bucket.
+    buckets: Vec<Vec<usize>>,
+    /// Size of mini batch.
+    batch_size: usize,
+    /// If `true`, the last batch of each bucket is dropped if its size is less than `batch_size`.
+    drop_last: bool,
+    /// If `true`, the indices are shuffled inside the buckets and the batches across the buckets.
+    shuffle: bool,
+}
+
+impl BucketBatchSampler {
+    /// Create a new [`BucketBatchSampler`].
+    ///
+    /// # Arguments
+    ///
+    /// * `lengths` - The length of each sample of the dataset.
+    /// * `bucket_boundaries` - The upper length boundaries of the buckets, in ascending order.
+    /// * `batch_size` - Size of mini-batch.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `bucket_boundaries` is not strictly increasing or if `batch_size` is zero.
+    #[must_use]
+    pub fn new(lengths: &[usize], bucket_boundaries: &[usize], batch_size: usize) -> Self {
+        assert!(
+            bucket_boundaries.windows(2).all(|pair| pair[0] < pair[1]),
+            "bucket boundaries should be strictly increasing"
+        );
+        assert!(batch_size > 0, "batch_size should be positive");
+        let mut buckets = vec![Vec::new(); bucket_boundaries.len() + 1];
+        for (idx, length) in lengths.iter().enumerate() {
+            let bucket = bucket_boundaries.partition_point(|boundary| boundary <= length);
+            buckets[bucket].push(idx);
+        }
+        Self {
+            buckets,
+            batch_size,
+            drop_last: false,
+            shuffle: true,
+        }
+    }
+
+    /// Create a new [`BucketBatchSampler`], computing the length of each sample of `dataset` with `length`.
+    ///
+    /// All the samples of the dataset are fetched once.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `bucket_boundaries` is not strictly increasing or if `batch_size` is zero.
+    pub fn from_dataset<D, F>(
+        dataset: &D,
+        length: F,
+        bucket_boundaries: &[usize],
+        batch_size: usize,
+    ) -> Self
+    where
+        D: Dataset,
+        F: Fn(D::Sample) -> usize,
+    {
+        let lengths: Vec<usize> = (0..dataset.len())
+            .map(|idx| length(dataset.get_sample(idx)))
+            .collect();
+        Self::new(&lengths, bucket_boundaries, batch_size)
+    }
+
+    /// Drop the last batch of each bucket if its size is less than `batch_size`.
+    #[must_use]
+    pub fn drop_last(mut self) -> Self {
+        self.drop_last = true;
+        self
+    }
+
+    /// Yield the batches bucket by bucket, in ascending order of the indices.
+    #[must_use]
+    pub fn without_shuffle(mut self) -> Self {
+        self.shuffle = false;
+        self
+    }
+}
+
+impl Len for BucketBatchSampler {
+    /// Returns the number of batch.
+    ///
+    /// If `drop_last` is set to false, even an incomplete batch will be counted for each bucket.
+    fn len(&self) -> usize {
+        self.buckets
+            .iter()
+            .map(|bucket| {
+                if self.drop_last {
+                    bucket.len() / self.batch_size
+                } else {
+                    (bucket.len() + self.batch_size - 1) / self.batch_size
+                }
+            })
+            .sum()
+    }
+}
+
+impl BatchSampling for BucketBatchSampler {
+    type Iter = std::vec::IntoIter<Vec<usize>>;
+    fn iter_with_rng(&self, rng: &mut StdRng) -> Self::Iter {
+        let mut batches = Vec::with_capacity(self.len());
+        for bucket in &self.buckets {
+            let mut bucket = bucket.clone();
+            if self.shuffle {
+                bucket.shuffle(rng);
+            }
+            batches.extend(
+                bucket
+                    .chunks(self.batch_size)
+                    .filter(|batch| !self.drop_last || batch.len() == self.batch_size)
+                    .map(<[usize]>::to_vec),
+            );
+        }
+        if self.shuffle {
+            batches.shuffle(rng);
+        }
+        batches.into_iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bucket_batch_sampler() {
+        let lengths = vec![1, 25, 7, 13, 2, 21, 8, 14, 3, 30];
+        let sampler = BucketBatchSampler::new(&lengths, &[5, 20], 2);
+        // Buckets: [0, 4, 8], [2, 3, 6, 7] and [1, 5, 9].
+        assert_eq!(sampler.len(), 6);
+        let mut iter = sampler.iter();
+        assert_eq!(iter.len(), 6);
+        let mut num_samples = 0;
+        for batch in iter.by_ref() {
+            let bucket = |idx: usize| [5, 20].partition_point(|boundary| *boundary <= lengths[idx]);
+            assert!(batch.iter().all(|idx| bucket(*idx) == bucket(batch[0])));
+            num_samples += batch.len();
+        }
+        assert_eq!(num_samples, lengths.len());
+    }
+
+    #[test]
+    fn without_shuffle() {
+        let lengths = vec![1, 25, 7, 13, 2, 21, 8, 14, 3, 30];
+        let sampler = BucketBatchSampler::new(&lengths, &[5, 20], 2).without_shuffle();
+        assert_eq!(
+            sampler.iter().collect::<Vec<_>>(),
+            vec![
+                vec![0, 4],
+                vec![8],
+                vec![2, 3],
+                vec![6, 7],
+                vec![1, 5],
+                vec![9]
+            ]
+        );
+    }
+
+    #[test]
+    fn drop_last() {
+        let lengths = vec![1, 25, 7, 13, 2, 21, 8, 14, 3, 30];
+        let sampler = BucketBatchSampler::new(&lengths, &[5, 20], 2).drop_last();
+        assert_eq!(sampler.len(), 4);
+        let batches: Vec<_> = sampler.iter().collect();
+        assert_eq!(batches.len(), 4);
+        assert!(batches.iter().all(|batch| batch.len() == 2));
+    }
+
+    #[test]
+    fn from_dataset() {
+        let dataset = vec![
+            String::from("a"),
+            String::from("a long sentence"),
+            String::from("ab"),
+            String::from("another long one"),
+        ];
+        let sampler = BucketBatchSampler::from_dataset(&dataset, |text| text.len(), &[10], 2)
+            .without_shuffle();
+        assert_eq!(
+            sampler.iter().collect::<Vec<_>>(),
+            vec![vec![0, 2], vec![1, 3]]
+        );
+    }
+}
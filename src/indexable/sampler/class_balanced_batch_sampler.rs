@@ -0,0 +1,313 @@
+//! Control the classes of the samples in each batch.
+
+use std::collections::HashMap;
+use std::hash::Hash;
+
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::Rng;
+
+use super::{BatchSampling, Len};
+
+/// How the batches are made up.
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd, Hash, Eq, Ord)]
+enum Strategy {
+    /// Each batch has `classes_per_batch` classes with `samples_per_class` samples each.
+    PerClass {
+        classes_per_batch: usize,
+        samples_per_class: usize,
+    },
+    /// Each batch has `batch_size` samples, the classes in proportion to their frequency in the dataset.
+    Stratified { batch_size: usize, drop_last: bool },
+}
+
+/// Batch sampler that controls the classes of the samples in each batch, from the label of each index.
+///
+/// There are two strategies:
+/// - [`ClassBalancedBatchSampler::per_class`] draws `P` classes and `K` samples of each class per batch,
+///   the so called P×K sampling used in metric learning.
+/// - [`ClassBalancedBatchSampler::stratified`] draws batches whose classes are in proportion to
+///   the class distribution of the dataset.
+///
+/// ```
+/// use ai_dataloader::sampler::{BatchSampling, ClassBalancedBatchSampler};
+/// use ai_dataloader::Len;
+///
+/// let labels = vec!["cat", "dog", "cat", "bird", "dog", "bird", "cat", "dog", "cat", "dog"];
+/// // Two classes and two samples of each class per batch.
+/// let sampler = ClassBalancedBatchSampler::per_class(&labels, 2, 2);
+/// assert_eq!(sampler.len(), 2);
+/// for batch in sampler.iter() {
+///     assert_eq!(batch.len(), 4);
+///     assert_eq!(labels[batch[0]], labels[batch[1]]);
+///     assert_eq!(labels[batch[2]], labels[batch[3]]);
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, PartialOrd, Hash, Eq, Ord)]
+pub struct ClassBalancedBatchSampler {
+    /// The indices of the dataset, grouped by class.
+    classes: Vec<Vec<usize>>,
+    /// How the batches are made up.
+    strategy: Strategy,
+}
+
+impl ClassBalancedBatchSampler {
+    /// Create a [`ClassBalancedBatchSampler`] yielding batches of `classes_per_batch` distinct classes, with
+    /// `samples_per_class` samples of each class. The samples of the same class are contiguous in the batch.
+    ///
+    /// Each index is drawn at most once per epoch, so the samples of a class that don't fill a group of `samples_per_class`
+    /// are left out. The classes with the most samples left are drawn first, which maximizes the number of batches.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `classes_per_batch` or `samples_per_class` is zero.
+    #[must_use]
+    pub fn per_class<L: Eq + Hash>(
+        labels: &[L],
+        classes_per_batch: usize,
+        samples_per_class: usize,
+    ) -> Self {
+        assert!(
+            classes_per_batch > 0 && samples_per_class > 0,
+            "classes_per_batch and samples_per_class should be positive"
+        );
+        Self {
+            classes: group_by_class(labels),
+            strategy: Strategy::PerClass {
+                classes_per_batch,
+                samples_per_class,
+            },
+        }
+    }
+
+    /// Create a [`ClassBalancedBatchSampler`] yielding batches of `batch_size` samples where each class appears
+    /// in proportion to its frequency in the dataset, up to rounding.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `batch_size` is zero.
+    #[must_use]
+    pub fn stratified<L: Eq + Hash>(labels: &[L], batch_size: usize) -> Self {
+        assert!(batch_size > 0, "batch_size should be positive");
+        Self {
+            classes: group_by_class(labels),
+            strategy: Strategy::Stratified {
+                batch_size,
+                drop_last: false,
+            },
+        }
+    }
+
+    /// Drop the last batch if its size is less than `batch_size`. Only the stratified batches can be incomplete.
+    #[must_use]
+    pub fn drop_last(mut self) -> Self {
+        if let Strategy::Stratified { drop_last, .. } = &mut self.strategy {
+            *drop_last = true;
+        }
+        self
+    }
+
+    /// Run the P×K scheduling on the number of groups of each class, calling `draw` with the classes of each batch.
+    ///
+    /// `order` is the order in which the classes with the same number of groups are considered.
+    fn schedule_per_class(
+        &self,
+        classes_per_batch: usize,
+        samples_per_class: usize,
+        mut order: Vec<usize>,
+        mut draw: impl FnMut(&[usize]),
+    ) {
+        let mut num_groups: Vec<usize> = self
+            .classes
+            .iter()
+            .map(|class| class.len() / samples_per_class)
+            .collect();
+        if order.len() < classes_per_batch {
+            return;
+        }
+        loop {
+            // The sort is stable: the classes with the same number of groups keep their order.
+            order.sort_by_key(|class| std::cmp::Reverse(num_groups[*class]));
+            let drawn = &order[..classes_per_batch];
+            if num_groups[drawn[classes_per_batch - 1]] == 0 {
+                return;
+            }
+            for class in drawn {
+                num_groups[*class] -= 1;
+            }
+            draw(drawn);
+        }
+    }
+}
+
+/// Group the indices by label, the classes being ordered by first appearance.
+fn group_by_class<L: Eq + Hash>(labels: &[L]) -> Vec<Vec<usize>> {
+    let mut class_ids: HashMap<&L, usize> = HashMap::new();
+    let mut classes: Vec<Vec<usize>> = Vec::new();
+    for (idx, label) in labels.iter().enumerate() {
+        let class_id = *class_ids.entry(label).or_insert_with(|| {
+            classes.push(Vec::new());
+            classes.len() - 1
+        });
+        classes[class_id].push(idx);
+    }
+    classes
+}
+
+impl Len for ClassBalancedBatchSampler {
+    /// Returns the exact number of batch.
+    fn len(&self) -> usize {
+        match self.strategy {
+            Strategy::PerClass {
+                classes_per_batch,
+                samples_per_class,
+            } => {
+                let mut len = 0;
+                self.schedule_per_class(
+                    classes_per_batch,
+                    samples_per_class,
+                    (0..self.classes.len()).collect(),
+                    |_| len += 1,
+                );
+                len
+            }
+            Strategy::Stratified {
+                batch_size,
+                drop_last,
+            } => {
+                let num_samples: usize = self.classes.iter().map(Vec::len).sum();
+                if drop_last {
+                    num_samples / batch_size
+                } else {
+                    (num_samples + batch_size - 1) / batch_size
+                }
+            }
+        }
+    }
+}
+
+impl BatchSampling for ClassBalancedBatchSampler {
+    type Iter = std::vec::IntoIter<Vec<usize>>;
+    fn iter_with_rng(&self, rng: &mut StdRng) -> Self::Iter {
+        let mut classes = self.classes.clone();
+        for class in &mut classes {
+            class.shuffle(rng);
+        }
+        match self.strategy {
+            Strategy::PerClass {
+                classes_per_batch,
+                samples_per_class,
+            } => {
+                let mut groups: Vec<_> = classes
+                    .iter()
+                    .map(|class| class.chunks_exact(samples_per_class))
+                    .collect();
+                let mut order: Vec<usize> = (0..classes.len()).collect();
+                order.shuffle(rng);
+                let mut batches = Vec::with_capacity(self.len());
+                self.schedule_per_class(classes_per_batch, samples_per_class, order, |drawn| {
+                    batches.push(
+                        drawn
+                            .iter()
+                            .flat_map(|class| {
+                                groups[*class].next().expect("the groups are counted")
+                            })
+                            .copied()
+                            .collect(),
+                    );
+                });
+                batches.shuffle(rng);
+                batches.into_iter()
+            }
+            Strategy::Stratified {
+                batch_size,
+                drop_last,
+            } => {
+                // The samples of a class are spread evenly over the epoch: the `j`-th sample of a class of `n` samples
+                // gets a key in `[j / n, (j + 1) / n)`, then the keys are sorted.
+                #[allow(clippy::cast_precision_loss)]
+                let mut keys: Vec<(f64, usize)> = classes
+                    .iter()
+                    .flat_map(|class| {
+                        let num_samples = class.len() as f64;
+                        class
+                            .iter()
+                            .enumerate()
+                            .map(|(j, idx)| ((j as f64 + rng.random::<f64>()) / num_samples, *idx))
+                            .collect::<Vec<_>>()
+                    })
+                    .collect();
+                keys.sort_unstable_by(|a, b| a.0.total_cmp(&b.0));
+                let indices: Vec<usize> = keys.into_iter().map(|(_, idx)| idx).collect();
+                indices
+                    .chunks(batch_size)
+                    .filter(|batch| !drop_last || batch.len() == batch_size)
+                    .map(<[usize]>::to_vec)
+                    .collect::<Vec<_>>()
+                    .into_iter()
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use itertools::Itertools;
+
+    #[test]
+    fn per_class() {
+        // Class 0 has 7 samples, class 1 has 4, class 2 has 2 and class 3 has 1.
+        let labels = vec![0, 1, 0, 0, 2, 1, 0, 3, 0, 1, 2, 0, 1, 0];
+        let sampler = ClassBalancedBatchSampler::per_class(&labels, 2, 2);
+        // Groups of two samples: 3 for the class 0, 2 for the class 1 and 1 for the class 2.
+        assert_eq!(sampler.len(), 3);
+        let batches: Vec<_> = sampler.iter().collect();
+        assert_eq!(batches.len(), 3);
+        for batch in &batches {
+            assert_eq!(batch.len(), 4);
+            assert_eq!(labels[batch[0]], labels[batch[1]]);
+            assert_eq!(labels[batch[2]], labels[batch[3]]);
+            assert_ne!(labels[batch[0]], labels[batch[2]]);
+        }
+        assert!(batches.concat().iter().all_unique());
+    }
+
+    #[test]
+    fn per_class_not_enough_classes() {
+        let labels = vec![0, 0, 1, 1];
+        assert_eq!(ClassBalancedBatchSampler::per_class(&labels, 3, 2).len(), 0);
+        assert_eq!(
+            ClassBalancedBatchSampler::per_class(&labels, 3, 2)
+                .iter()
+                .count(),
+            0
+        );
+    }
+
+    #[test]
+    fn stratified() {
+        let labels: Vec<_> = (0..100).map(|idx| usize::from(idx % 4 == 0)).collect();
+        let sampler = ClassBalancedBatchSampler::stratified(&labels, 8);
+        assert_eq!(sampler.len(), 13);
+        let batches: Vec<_> = sampler.iter().collect();
+        assert_eq!(batches.len(), 13);
+        // A quarter of the samples are of the class 1, that is two samples per complete batch, up to rounding.
+        for batch in &batches[..12] {
+            assert_eq!(batch.len(), 8);
+            let count = batch.iter().filter(|idx| labels[**idx] == 1).count();
+            assert!((1..=3).contains(&count));
+        }
+        let mut indices = batches.concat();
+        indices.sort_unstable();
+        assert_eq!(indices, (0..100).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn stratified_drop_last() {
+        let labels = vec!['a', 'b', 'a', 'b', 'a'];
+        let sampler = ClassBalancedBatchSampler::stratified(&labels, 2).drop_last();
+        assert_eq!(sampler.len(), 2);
+        assert!(sampler.iter().all(|batch| batch.len() == 2));
+    }
+}
@@ -0,0 +1,219 @@
+//! Restrict the sampling to a subset of the dataset, for distributed data parallel training.
+
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
+
+use super::{Len, Sampler};
+
+/// Sampler that restricts data loading to a subset of the dataset, like `torch.utils.data.distributed.DistributedSampler`.
+///
+/// Each process of a distributed training (each replica) gets a disjoint, equally sized slice of the indices.
+/// If the length of the dataset doesn't divide evenly, the indices are padded by wrapping around,
+/// unless `drop_last` is set, in which case the tail of the indices is dropped.
+///
+/// The shuffling only depends on `seed` and on the epoch, so that all the replicas use the same permutation.
+/// Call [`Sampler::set_epoch`] at the beginning of each epoch to get a different permutation each time.
+///
+/// ```
+/// use ai_dataloader::sampler::{DistributedSampler, Sampler};
+///
+/// let rank_0 = DistributedSampler::new(5, 2, 0).without_shuffle();
+/// let rank_1 = DistributedSampler::new(5, 2, 1).without_shuffle();
+/// assert_eq!(rank_0.iter().collect::<Vec<_>>(), vec![0, 2, 4]);
+/// // The indices are padded with the first ones.
+/// assert_eq!(rank_1.iter().collect::<Vec<_>>(), vec![1, 3, 0]);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd, Hash, Eq, Ord)]
+pub struct DistributedSampler {
+    /// The length of the dataset that will be sampled.
+    data_source_len: usize,
+    /// Number of processes participating in the training, also called world size.
+    num_replicas: usize,
+    /// Rank of the current process, between zero and `num_replicas`.
+    rank: usize,
+    /// If `true`, the indices are shuffled.
+    shuffle: bool,
+    /// Seed used to shuffle the indices, it must be identical across all the processes.
+    seed: u64,
+    /// If `true`, the tail of the indices is dropped to make them evenly divisible across the replicas.
+    drop_last: bool,
+    /// Current epoch, the permutation is drawn from `seed + epoch`.
+    epoch: u64,
+}
+
+impl DistributedSampler {
+    /// Create a new [`DistributedSampler`], shuffling the indices with a seed of zero.
+    ///
+    /// # Arguments
+    ///
+    /// * `data_source_len` - The length of the dataset.
+    /// * `num_replicas` - Number of processes participating in the training (the world size).
+    /// * `rank` - Rank of the current process.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `rank` is not lower than `num_replicas`.
+    #[must_use]
+    pub fn new(data_source_len: usize, num_replicas: usize, rank: usize) -> Self {
+        assert!(
+            rank < num_replicas,
+            "invalid rank {rank}, rank should be in the interval [0, {num_replicas})"
+        );
+        Self {
+            data_source_len,
+            num_replicas,
+            rank,
+            shuffle: true,
+            seed: 0,
+            drop_last: false,
+            epoch: 0,
+        }
+    }
+
+    /// Yield the indices in ascending order.
+    #[must_use]
+    pub fn without_shuffle(mut self) -> Self {
+        self.shuffle = false;
+        self
+    }
+
+    /// Set the seed used to shuffle the indices. It must be identical across all the processes.
+    #[must_use]
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    /// Drop the tail of the indices to make them evenly divisible across the replicas, instead of padding them.
+    #[must_use]
+    pub fn drop_last(mut self) -> Self {
+        self.drop_last = true;
+        self
+    }
+
+    /// The total number of indices across all the replicas, after padding or dropping.
+    fn total_size(&self) -> usize {
+        self.len() * self.num_replicas
+    }
+}
+
+impl Len for DistributedSampler {
+    /// Returns the number of indices yielded to the current replica.
+    fn len(&self) -> usize {
+        if self.drop_last {
+            self.data_source_len / self.num_replicas
+        } else {
+            (self.data_source_len + self.num_replicas - 1) / self.num_replicas
+        }
+    }
+}
+
+impl Sampler for DistributedSampler {
+    type Iter = std::vec::IntoIter<usize>;
+
+    /// The generator of the loader is ignored, the permutation only depends on `seed` and on the epoch.
+    fn iter_with_rng(&self, _rng: &mut StdRng) -> Self::Iter {
+        let mut indices: Vec<usize> = (0..self.data_source_len).collect();
+        if self.shuffle {
+            indices.shuffle(&mut StdRng::seed_from_u64(
+                self.seed.wrapping_add(self.epoch),
+            ));
+        }
+        let total_size = self.total_size();
+        // An empty dataset has nothing to pad with.
+        if total_size > indices.len() && !indices.is_empty() {
+            let padding: Vec<usize> = indices
+                .iter()
+                .copied()
+                .cycle()
+                .take(total_size - indices.len())
+                .collect();
+            indices.extend(padding);
+        } else {
+            indices.truncate(total_size);
+        }
+        indices
+            .into_iter()
+            .skip(self.rank)
+            .step_by(self.num_replicas)
+            .collect::<Vec<_>>()
+            .into_iter()
+    }
+
+    fn set_epoch(&mut self, epoch: u64) {
+        self.epoch = epoch;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use itertools::Itertools;
+
+    fn all_ranks(
+        sampler: impl Fn(usize) -> DistributedSampler,
+        num_replicas: usize,
+    ) -> Vec<Vec<usize>> {
+        (0..num_replicas)
+            .map(|rank| sampler(rank).iter().collect())
+            .collect()
+    }
+
+    #[test]
+    fn distributed_sampler() {
+        let indices = all_ranks(|rank| DistributedSampler::new(10, 3, rank), 3);
+        for rank_indices in &indices {
+            assert_eq!(rank_indices.len(), 4);
+        }
+        // 10 indices padded to 12: two indices are seen twice.
+        let counts = indices.concat().into_iter().counts();
+        assert_eq!(counts.len(), 10);
+        assert_eq!(counts.values().filter(|count| **count == 2).count(), 2);
+    }
+
+    #[test]
+    fn drop_last() {
+        let indices = all_ranks(|rank| DistributedSampler::new(10, 3, rank).drop_last(), 3);
+        for rank_indices in &indices {
+            assert_eq!(rank_indices.len(), 3);
+        }
+        assert!(indices.concat().iter().all_unique());
+        assert_eq!(DistributedSampler::new(10, 3, 0).drop_last().len(), 3);
+    }
+
+    #[test]
+    fn without_shuffle() {
+        let indices = all_ranks(
+            |rank| DistributedSampler::new(7, 3, rank).without_shuffle(),
+            3,
+        );
+        assert_eq!(indices, vec![vec![0, 3, 6], vec![1, 4, 0], vec![2, 5, 1]]);
+    }
+
+    #[test]
+    fn more_replicas_than_samples() {
+        let indices = all_ranks(
+            |rank| DistributedSampler::new(2, 5, rank).without_shuffle(),
+            5,
+        );
+        assert_eq!(indices, vec![vec![0], vec![1], vec![0], vec![1], vec![0]]);
+    }
+
+    #[test]
+    fn set_epoch() {
+        let mut sampler = DistributedSampler::new(100, 2, 0).seed(42);
+        let first: Vec<_> = sampler.iter().collect();
+        assert_eq!(sampler.iter().collect::<Vec<_>>(), first);
+        sampler.set_epoch(1);
+        assert_ne!(sampler.iter().collect::<Vec<_>>(), first);
+        sampler.set_epoch(0);
+        assert_eq!(sampler.iter().collect::<Vec<_>>(), first);
+    }
+
+    #[test]
+    #[should_panic(expected = "invalid rank 2")]
+    fn invalid_rank() {
+        let _sampler = DistributedSampler::new(10, 2, 2);
+    }
+}
@@ -0,0 +1,193 @@
+//! Yield a random permutation of the indices without storing it.
+
+use rand::rngs::StdRng;
+use rand::Rng;
+
+use super::{FromLen, Len, Sampler};
+
+/// Number of rounds of the Feistel network.
+const NUM_ROUNDS: usize = 6;
+
+/// Sampler that yields each index from zero to `data_source_len` exactly once, in a pseudo-random order,
+/// using a constant amount of memory.
+///
+/// Unlike the [`RandomSampler`](super::RandomSampler) that shuffles a list of all the indices, the permutation is
+/// computed lazily: each index is the image of its position by a keyed bijection. It's useful for datasets too large
+/// to hold all their indices in memory. The keys are drawn from the random number generator of the loader,
+/// so a seeded loader gives a reproducible permutation.
+///
+/// The permutation is pseudo-random: it's good enough to shuffle a dataset, but doesn't have the statistical quality
+/// of a true uniform shuffle.
+///
+/// ```
+/// use ai_dataloader::sampler::{LazyRandomSampler, Sampler};
+///
+/// let sampler = LazyRandomSampler::new(5);
+/// let mut indices: Vec<_> = sampler.iter().collect();
+/// indices.sort_unstable();
+/// assert_eq!(indices, vec![0, 1, 2, 3, 4]);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd, Hash, Eq, Ord)]
+pub struct LazyRandomSampler {
+    /// The length of the dataset that will be sampled.
+    data_source_len: usize,
+}
+
+impl LazyRandomSampler {
+    /// Create a new [`LazyRandomSampler`] over a dataset of length `data_source_len`.
+    #[must_use]
+    pub fn new(data_source_len: usize) -> Self {
+        Self { data_source_len }
+    }
+}
+
+impl FromLen for LazyRandomSampler {
+    fn from_len(data_source_len: usize) -> Self {
+        Self::new(data_source_len)
+    }
+}
+
+impl Len for LazyRandomSampler {
+    fn len(&self) -> usize {
+        self.data_source_len
+    }
+}
+
+impl Sampler for LazyRandomSampler {
+    type Iter = LazyRandomSamplerIter;
+    fn iter_with_rng(&self, rng: &mut StdRng) -> Self::Iter {
+        LazyRandomSamplerIter::new(self.data_source_len, rng)
+    }
+}
+
+/// Iterator that returns the indices of a [`LazyRandomSampler`].
+#[derive(Debug)]
+pub struct LazyRandomSamplerIter {
+    /// The number of indices to permute.
+    len: usize,
+    /// The position of the next index in the permutation.
+    position: usize,
+    /// Number of bits of each half of the Feistel network domain.
+    half_bits: u32,
+    /// Key of each round of the Feistel network.
+    keys: [u64; NUM_ROUNDS],
+}
+
+impl LazyRandomSamplerIter {
+    fn new<R: Rng + ?Sized>(len: usize, rng: &mut R) -> Self {
+        // The Feistel network permutes the smallest domain of `2 * half_bits` bits containing all the indices,
+        // that is less than four times `len`.
+        let bits = usize::BITS - len.saturating_sub(1).leading_zeros();
+        let half_bits = ((bits + 1) / 2).max(1);
+        Self {
+            len,
+            position: 0,
+            half_bits,
+            keys: rng.random(),
+        }
+    }
+
+    /// The image of `x` by the Feistel network, a bijection over `[0, 2^(2 * half_bits))`.
+    fn feistel(&self, x: u64) -> u64 {
+        let mask = (1 << self.half_bits) - 1;
+        let mut left = x >> self.half_bits;
+        let mut right = x & mask;
+        for key in self.keys {
+            let round = left ^ (mix(right ^ key) & mask);
+            left = right;
+            right = round;
+        }
+        (left << self.half_bits) | right
+    }
+
+    /// The image of `position` by a bijection over `[0, len)`.
+    ///
+    /// The Feistel network is applied until the result falls into the indices ("cycle walking"),
+    /// which takes less than four rounds on average.
+    fn permute(&self, position: usize) -> usize {
+        let len = self.len as u64;
+        let mut x = position as u64;
+        loop {
+            x = self.feistel(x);
+            if x < len {
+                #[allow(clippy::cast_possible_truncation)]
+                // `x` is lower than `len`, so it fits in a `usize`.
+                return x as usize;
+            }
+        }
+    }
+}
+
+/// Mix the bits of `x`, the finalizer of `SplitMix64`.
+fn mix(mut x: u64) -> u64 {
+    x = (x ^ (x >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    x ^ (x >> 31)
+}
+
+impl Iterator for LazyRandomSamplerIter {
+    type Item = usize;
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.position == self.len {
+            return None;
+        }
+        let idx = self.permute(self.position);
+        self.position += 1;
+        Some(idx)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.len - self.position;
+        (len, Some(len))
+    }
+}
+
+impl ExactSizeIterator for LazyRandomSamplerIter {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use itertools::Itertools;
+    use rand::SeedableRng;
+
+    #[test]
+    fn permutation() {
+        for len in [0, 1, 2, 3, 5, 16, 17, 100, 1000] {
+            let mut indices: Vec<_> = LazyRandomSampler::new(len).iter().collect();
+            indices.sort_unstable();
+            assert_eq!(indices, (0..len).collect::<Vec<_>>());
+        }
+    }
+
+    #[test]
+    fn shuffled() {
+        let sampler = LazyRandomSampler::new(1000);
+        let first: Vec<_> = sampler.iter().collect();
+        assert_ne!(first, (0..1000).collect::<Vec<_>>());
+        assert_ne!(sampler.iter().collect::<Vec<_>>(), first);
+    }
+
+    #[test]
+    fn seeded() {
+        let sampler = LazyRandomSampler::new(100);
+        assert_eq!(
+            sampler
+                .iter_with_rng(&mut StdRng::seed_from_u64(42))
+                .collect::<Vec<_>>(),
+            sampler
+                .iter_with_rng(&mut StdRng::seed_from_u64(42))
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn billion_scale() {
+        let sampler = LazyRandomSampler::new(10_000_000_000);
+        let mut iter = sampler.iter();
+        assert_eq!(iter.len(), 10_000_000_000);
+        let indices: Vec<_> = iter.by_ref().take(1000).collect();
+        assert!(indices.iter().all_unique());
+        assert!(indices.iter().all(|idx| *idx < 10_000_000_000));
+        assert_eq!(iter.len(), 10_000_000_000 - 1000);
+    }
+}
@@ -0,0 +1,36 @@
+use rand::rngs::StdRng;
+
+use super::BatchSampling;
+use crate::Len;
+
+/// Batch sampler yielding a list of batches computed beforehand, always the same batches in the same order.
+///
+/// ```
+/// use ai_dataloader::sampler::{BatchSampling, PrecomputedBatches};
+///
+/// let batch_sampler = PrecomputedBatches(vec![vec![0, 1, 2], vec![3, 4]]);
+/// let mut iter = batch_sampler.iter();
+/// assert_eq!(iter.next(), Some(vec![0, 1, 2]));
+/// assert_eq!(iter.next(), Some(vec![3, 4]));
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, PartialOrd, Hash, Eq, Ord)]
+pub struct PrecomputedBatches(pub Vec<Vec<usize>>);
+
+impl From<Vec<Vec<usize>>> for PrecomputedBatches {
+    fn from(batches: Vec<Vec<usize>>) -> Self {
+        Self(batches)
+    }
+}
+
+impl Len for PrecomputedBatches {
+    fn len(&self) -> usize {
+        self.0.len()
+    }
+}
+
+impl BatchSampling for PrecomputedBatches {
+    type Iter = std::vec::IntoIter<Vec<usize>>;
+    fn iter_with_rng(&self, _rng: &mut StdRng) -> Self::Iter {
+        self.0.clone().into_iter()
+    }
+}
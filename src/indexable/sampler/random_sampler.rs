@@ -1,9 +1,23 @@
-use rand::rng;
+use rand::rngs::StdRng;
 use rand::seq::SliceRandom;
+use rand::{rng, Rng};
 
-use super::{Len, Sampler};
+use super::{FromLen, Len, Sampler};
 
 /// Sampler that returns random index between zero and `data_source_len`.
+///
+/// Without replacement, each index is yielded once per iteration. If more samples than the
+/// dataset length are requested, several permutations are drawn one after the other.
+/// With replacement, indices are drawn independently so the same index can be yielded several times.
+///
+/// ```
+/// use ai_dataloader::sampler::RandomSampler;
+/// use ai_dataloader::Len;
+///
+/// let sampler = RandomSampler::new(10).replacement().num_samples(25);
+/// assert_eq!(sampler.len(), 25);
+/// assert!(sampler.into_iter().all(|idx| idx < 10));
+/// ```
 #[derive(Debug, Clone, Copy, PartialEq, PartialOrd, Hash, Eq, Ord)]
 pub struct RandomSampler {
     /// The length of the data source.
@@ -11,26 +25,60 @@ pub struct RandomSampler {
     /// Whether the sample is replaced or not.
     /// If it's replaced, we can have 2 times the same sample.
     replacement: bool,
+    /// Number of samples to draw, default to `data_source_len`.
+    num_samples: Option<usize>,
 }
 
-impl Sampler for RandomSampler {
-    fn new(data_source_len: usize) -> Self {
+impl RandomSampler {
+    /// Create a new [`RandomSampler`] over a dataset of length `data_source_len`.
+    #[must_use]
+    pub fn new(data_source_len: usize) -> Self {
         Self {
             data_source_len,
             replacement: false,
+            num_samples: None,
         }
     }
+    /// Draw the samples with replacement: the same index can be yielded several times during one iteration.
+    #[must_use]
+    pub fn replacement(mut self) -> Self {
+        self.replacement = true;
+        self
+    }
+    /// Set the number of indices yielded by one iteration, which can be more or less than the dataset length.
+    #[must_use]
+    pub fn num_samples(mut self, num_samples: usize) -> Self {
+        self.num_samples = Some(num_samples);
+        self
+    }
+}
+
+impl Sampler for RandomSampler {
+    type Iter = RandomSamplerIter;
+    fn iter_with_rng(&self, rng: &mut StdRng) -> Self::Iter {
+        RandomSamplerIter::new(*self, rng)
+    }
+}
+
+impl FromLen for RandomSampler {
+    fn from_len(data_source_len: usize) -> Self {
+        Self::new(data_source_len)
+    }
 }
 impl Len for RandomSampler {
     fn len(&self) -> usize {
-        self.data_source_len
+        // Nothing can be drawn from an empty dataset.
+        if self.data_source_len == 0 {
+            return 0;
+        }
+        self.num_samples.unwrap_or(self.data_source_len)
     }
 }
 impl IntoIterator for RandomSampler {
     type Item = usize;
     type IntoIter = RandomSamplerIter;
     fn into_iter(self) -> Self::IntoIter {
-        RandomSamplerIter::new(self.data_source_len, self.replacement)
+        RandomSamplerIter::new(self, &mut rng())
     }
 }
 /// Iterator that returns random index between zero and `data_source_len`.
@@ -47,21 +95,25 @@ impl RandomSamplerIter {
     ///
     /// # Arguments
     ///
-    /// * `data_source_len` - The length of the dataset.
-    /// * `replacement` - Whether we can have the same sample twice over one iteration or not.
-    // FIXME: change this parameters in the next breaking release
-    #[allow(clippy::fn_params_excessive_bools)]
-    fn new(data_source_len: usize, replacement: bool) -> Self {
-        if replacement {
-            todo!()
+    /// * `sampler` - The sampler that gives the length of the dataset and how to draw the indices.
+    /// * `rng` - The random number generator used to draw the indices.
+    fn new<R: Rng + ?Sized>(sampler: RandomSampler, rng: &mut R) -> Self {
+        let num_samples = sampler.len();
+        let indexes = if sampler.replacement {
+            (0..num_samples)
+                .map(|_| rng.random_range(0..sampler.data_source_len))
+                .collect()
         } else {
-            let mut vec: Vec<usize> = (0..data_source_len).collect();
-            vec.shuffle(&mut rng());
-            Self {
-                indexes: vec,
-                idx: 0,
+            let mut indexes = Vec::with_capacity(num_samples);
+            while indexes.len() < num_samples {
+                let mut permutation: Vec<usize> = (0..sampler.data_source_len).collect();
+                permutation.shuffle(rng);
+                permutation.truncate(num_samples - indexes.len());
+                indexes.append(&mut permutation);
             }
-        }
+            indexes
+        };
+        Self { indexes, idx: 0 }
     }
 }
 
@@ -87,24 +139,36 @@ impl ExactSizeIterator for RandomSamplerIter {}
 #[cfg(test)]
 mod tests {
     use super::*;
+    use itertools::Itertools;
+    use rand::SeedableRng;
 
     #[test]
     fn random_sampler() {
-        let random_sampler = RandomSampler {
-            data_source_len: 10,
-            replacement: false,
-        };
+        let random_sampler = RandomSampler::new(10);
         for idx in random_sampler {
             println!("{idx}");
         }
     }
 
+    #[test]
+    fn seeded() {
+        let random_sampler = RandomSampler::new(10);
+        let first: Vec<_> = random_sampler
+            .iter_with_rng(&mut StdRng::seed_from_u64(42))
+            .collect();
+        let second: Vec<_> = random_sampler
+            .iter_with_rng(&mut StdRng::seed_from_u64(42))
+            .collect();
+        assert_eq!(first, second);
+
+        let mut sorted = first.clone();
+        sorted.sort_unstable();
+        assert_eq!(sorted, (0..10).collect::<Vec<_>>());
+    }
+
     #[test]
     fn len() {
-        let random_sampler = RandomSampler {
-            data_source_len: 10,
-            replacement: false,
-        };
+        let random_sampler = RandomSampler::new(10);
 
         assert_eq!(random_sampler.len(), 10);
         let mut iter = random_sampler.into_iter();
@@ -115,4 +179,49 @@ mod tests {
         let _ = iter.next();
         assert_eq!(iter.len(), 7);
     }
+
+    #[test]
+    fn num_samples() {
+        let random_sampler = RandomSampler::new(10).num_samples(4);
+        assert_eq!(random_sampler.len(), 4);
+        let indices: Vec<_> = random_sampler.into_iter().collect();
+        assert_eq!(indices.len(), 4);
+        assert!(indices.iter().all(|&idx| idx < 10));
+        assert!(indices.iter().all_unique());
+
+        // Each block of `data_source_len` indices is a full permutation.
+        let random_sampler = RandomSampler::new(10).num_samples(25);
+        assert_eq!(random_sampler.len(), 25);
+        let indices: Vec<_> = random_sampler.into_iter().collect();
+        assert_eq!(indices.len(), 25);
+        for chunk in indices.chunks(10) {
+            assert!(chunk.iter().all_unique());
+        }
+    }
+
+    #[test]
+    fn replacement() {
+        let random_sampler = RandomSampler::new(3).replacement().num_samples(100);
+        assert_eq!(random_sampler.len(), 100);
+        let mut iter = random_sampler.into_iter();
+        assert_eq!(iter.len(), 100);
+        let indices: Vec<_> = iter.by_ref().collect();
+        assert!(indices.iter().all(|&idx| idx < 3));
+        // With 100 draws among 3 indices, duplicates are certain.
+        assert!(!indices.iter().all_unique());
+        assert_eq!(iter.len(), 0);
+
+        let random_sampler = RandomSampler::new(10).replacement();
+        assert_eq!(random_sampler.len(), 10);
+        assert_eq!(random_sampler.into_iter().count(), 10);
+    }
+
+    #[test]
+    fn empty() {
+        let random_sampler = RandomSampler::new(0).replacement().num_samples(5);
+        assert_eq!(random_sampler.len(), 0);
+        assert_eq!(random_sampler.into_iter().next(), None);
+        let random_sampler = RandomSampler::new(0).num_samples(5);
+        assert_eq!(random_sampler.into_iter().next(), None);
+    }
 }
@@ -0,0 +1,396 @@
+//! Adapters to combine and transform samplers.
+
+use std::fmt;
+
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+use super::{Len, Sampler};
+
+/// Adapters available on every [`Sampler`].
+///
+/// The adapters are samplers themselves, so they can be nested and given to a [`BatchSampler`](super::BatchSampler)
+/// or to the [`DataLoader`](crate::indexable::DataLoader) builder's `with_sampler` method.
+///
+/// ```
+/// use ai_dataloader::sampler::{Sampler, SamplerExt, SequentialSampler};
+///
+/// let sampler = SequentialSampler::new(4)
+///     .filter(|idx| idx % 2 == 0)
+///     .chain(SequentialSampler::new(2))
+///     .repeat(2);
+/// assert_eq!(sampler.iter().collect::<Vec<_>>(), vec![0, 2, 0, 1, 0, 2, 0, 1]);
+/// ```
+pub trait SamplerExt: Sampler + Sized {
+    /// Iterate `times` times over the sampler. Each pass draws a new order from the random number generator.
+    #[must_use]
+    fn repeat(self, times: usize) -> Repeat<Self> {
+        Repeat {
+            sampler: self,
+            times: Some(times),
+        }
+    }
+
+    /// Iterate over the sampler forever, for step-based training loops without epochs.
+    ///
+    /// Each pass draws a new order from the random number generator. The length of an infinite sampler is `usize::MAX`.
+    ///
+    /// ```
+    /// use ai_dataloader::indexable::DataLoader;
+    /// use ai_dataloader::sampler::{RandomSampler, SamplerExt};
+    ///
+    /// let loader = DataLoader::builder(vec![1, 2, 3, 4, 5])
+    ///     .with_sampler(RandomSampler::new(5).cycle())
+    ///     .batch_size(2)
+    ///     .build();
+    /// // Train for a number of steps rather than epochs.
+    /// assert_eq!(loader.iter().take(100).count(), 100);
+    /// ```
+    #[must_use]
+    fn cycle(self) -> Repeat<Self> {
+        Repeat {
+            sampler: self,
+            times: None,
+        }
+    }
+
+    /// Yield at most `num_indices` indices.
+    #[must_use]
+    fn take(self, num_indices: usize) -> Take<Self> {
+        Take {
+            sampler: self,
+            num_indices,
+        }
+    }
+
+    /// Only yield the indices for which `predicate` returns `true`.
+    ///
+    /// As the indices are filtered lazily, the length of the sampler is an upper bound: the one of the base sampler.
+    #[must_use]
+    fn filter<P>(self, predicate: P) -> Filter<Self, P>
+    where
+        P: Fn(&usize) -> bool + Clone,
+    {
+        Filter {
+            sampler: self,
+            predicate,
+        }
+    }
+
+    /// Yield the indices of this sampler, then the ones of `other`.
+    #[must_use]
+    fn chain<S: Sampler>(self, other: S) -> Chain<Self, S> {
+        Chain {
+            first: self,
+            second: other,
+        }
+    }
+
+    /// Alternate between the indices of this sampler and the ones of `other`.
+    /// Once one of them is exhausted, the remaining indices of the other are yielded.
+    #[must_use]
+    fn interleave<S: Sampler>(self, other: S) -> Interleave<Self, S> {
+        Interleave {
+            first: self,
+            second: other,
+        }
+    }
+}
+
+impl<S: Sampler> SamplerExt for S {}
+
+/// Sampler iterating several times, or forever, over a base sampler. See [`SamplerExt::repeat`] and [`SamplerExt::cycle`].
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd, Hash, Eq, Ord)]
+pub struct Repeat<S> {
+    /// Base sampler.
+    sampler: S,
+    /// Number of passes over the base sampler, `None` if infinite.
+    times: Option<usize>,
+}
+
+impl<S: Sampler> Len for Repeat<S> {
+    /// Returns the number of indices of all the passes, `usize::MAX` if the sampler is infinite.
+    fn len(&self) -> usize {
+        match self.times {
+            Some(times) => times.saturating_mul(self.sampler.len()),
+            None if self.sampler.len() == 0 => 0,
+            None => usize::MAX,
+        }
+    }
+}
+
+impl<S: Sampler + Clone> Sampler for Repeat<S> {
+    type Iter = RepeatIter<S>;
+    fn iter_with_rng(&self, rng: &mut StdRng) -> Self::Iter {
+        RepeatIter {
+            sampler: self.sampler.clone(),
+            rng: StdRng::from_rng(rng),
+            current: None,
+            remaining_passes: self.times,
+        }
+    }
+    fn set_epoch(&mut self, epoch: u64) {
+        self.sampler.set_epoch(epoch);
+    }
+}
+
+/// Iterator that returns the indices of a [`Repeat`] sampler.
+#[derive(Debug)]
+pub struct RepeatIter<S: Sampler> {
+    /// Base sampler, iterated at each pass.
+    sampler: S,
+    /// Random number generator given to the base sampler at each pass.
+    rng: StdRng,
+    /// Iterator of the current pass.
+    current: Option<S::Iter>,
+    /// Number of passes not started yet, `None` if infinite.
+    remaining_passes: Option<usize>,
+}
+
+impl<S: Sampler> Iterator for RepeatIter<S> {
+    type Item = usize;
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(idx) = self.current.as_mut().and_then(Iterator::next) {
+            return Some(idx);
+        }
+        match &mut self.remaining_passes {
+            Some(0) => return None,
+            Some(remaining_passes) => *remaining_passes -= 1,
+            None => {}
+        }
+        // If a new pass is empty, the base sampler yields nothing: the iteration stops instead of looping forever.
+        let mut pass = self.sampler.iter_with_rng(&mut self.rng);
+        let idx = pass.next();
+        self.current = Some(pass);
+        idx
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let (lower, upper) = self
+            .current
+            .as_ref()
+            .map_or((0, Some(0)), Iterator::size_hint);
+        let upper = self.remaining_passes.and_then(|remaining_passes| {
+            remaining_passes
+                .checked_mul(self.sampler.len())?
+                .checked_add(upper?)
+        });
+        (lower, upper)
+    }
+}
+
+/// Sampler yielding at most a given number of indices of a base sampler. See [`SamplerExt::take`].
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd, Hash, Eq, Ord)]
+pub struct Take<S> {
+    /// Base sampler.
+    sampler: S,
+    /// Maximum number of indices.
+    num_indices: usize,
+}
+
+impl<S: Sampler> Len for Take<S> {
+    fn len(&self) -> usize {
+        self.sampler.len().min(self.num_indices)
+    }
+}
+
+impl<S: Sampler> Sampler for Take<S> {
+    type Iter = std::iter::Take<S::Iter>;
+    fn iter_with_rng(&self, rng: &mut StdRng) -> Self::Iter {
+        self.sampler.iter_with_rng(rng).take(self.num_indices)
+    }
+    fn set_epoch(&mut self, epoch: u64) {
+        self.sampler.set_epoch(epoch);
+    }
+}
+
+/// Sampler yielding the indices of a base sampler that satisfy a predicate. See [`SamplerExt::filter`].
+#[derive(Clone)]
+pub struct Filter<S, P> {
+    /// Base sampler.
+    sampler: S,
+    /// The indices for which it returns `false` are skipped.
+    predicate: P,
+}
+
+impl<S: fmt::Debug, P> fmt::Debug for Filter<S, P> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Filter")
+            .field("sampler", &self.sampler)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<S: Sampler, P> Len for Filter<S, P> {
+    /// Returns an upper bound of the number of indices, the length of the base sampler.
+    fn len(&self) -> usize {
+        self.sampler.len()
+    }
+}
+
+impl<S, P> Sampler for Filter<S, P>
+where
+    S: Sampler,
+    P: Fn(&usize) -> bool + Clone,
+{
+    type Iter = std::iter::Filter<S::Iter, P>;
+    fn iter_with_rng(&self, rng: &mut StdRng) -> Self::Iter {
+        self.sampler
+            .iter_with_rng(rng)
+            .filter(self.predicate.clone())
+    }
+    fn set_epoch(&mut self, epoch: u64) {
+        self.sampler.set_epoch(epoch);
+    }
+}
+
+/// Sampler yielding the indices of a sampler, then the ones of another. See [`SamplerExt::chain`].
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd, Hash, Eq, Ord)]
+pub struct Chain<A, B> {
+    /// Sampler iterated first.
+    first: A,
+    /// Sampler iterated second.
+    second: B,
+}
+
+impl<A: Sampler, B: Sampler> Len for Chain<A, B> {
+    fn len(&self) -> usize {
+        self.first.len().saturating_add(self.second.len())
+    }
+}
+
+impl<A: Sampler, B: Sampler> Sampler for Chain<A, B> {
+    type Iter = std::iter::Chain<A::Iter, B::Iter>;
+    fn iter_with_rng(&self, rng: &mut StdRng) -> Self::Iter {
+        let first = self.first.iter_with_rng(rng);
+        first.chain(self.second.iter_with_rng(rng))
+    }
+    fn set_epoch(&mut self, epoch: u64) {
+        self.first.set_epoch(epoch);
+        self.second.set_epoch(epoch);
+    }
+}
+
+/// Sampler alternating between the indices of two samplers. See [`SamplerExt::interleave`].
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd, Hash, Eq, Ord)]
+pub struct Interleave<A, B> {
+    /// Sampler yielding the first index.
+    first: A,
+    /// Sampler yielding the second index.
+    second: B,
+}
+
+impl<A: Sampler, B: Sampler> Len for Interleave<A, B> {
+    fn len(&self) -> usize {
+        self.first.len().saturating_add(self.second.len())
+    }
+}
+
+impl<A: Sampler, B: Sampler> Sampler for Interleave<A, B> {
+    type Iter = itertools::Interleave<A::Iter, B::Iter>;
+    fn iter_with_rng(&self, rng: &mut StdRng) -> Self::Iter {
+        let first = self.first.iter_with_rng(rng);
+        itertools::interleave(first, self.second.iter_with_rng(rng))
+    }
+    fn set_epoch(&mut self, epoch: u64) {
+        self.first.set_epoch(epoch);
+        self.second.set_epoch(epoch);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sampler::{BatchSampler, DistributedSampler, RandomSampler, SequentialSampler};
+    use itertools::Itertools;
+
+    #[test]
+    fn repeat() {
+        let sampler = SequentialSampler::new(3).repeat(2);
+        assert_eq!(sampler.len(), 6);
+        let mut iter = sampler.iter();
+        assert_eq!(iter.size_hint(), (0, Some(6)));
+        assert_eq!(iter.by_ref().collect::<Vec<_>>(), vec![0, 1, 2, 0, 1, 2]);
+        assert_eq!(iter.next(), None);
+        assert_eq!(SequentialSampler::new(3).repeat(0).iter().count(), 0);
+    }
+
+    #[test]
+    fn repeat_draws_new_orders() {
+        let sampler = RandomSampler::new(50).repeat(2);
+        let indices: Vec<_> = sampler.iter().collect();
+        assert_eq!(indices.len(), 100);
+        assert_ne!(indices[..50], indices[50..]);
+        assert!(indices[..50].iter().all_unique());
+    }
+
+    #[test]
+    fn cycle() {
+        let sampler = SequentialSampler::new(3).cycle();
+        assert_eq!(sampler.len(), usize::MAX);
+        assert_eq!(
+            sampler.iter().take(7).collect::<Vec<_>>(),
+            vec![0, 1, 2, 0, 1, 2, 0]
+        );
+        // An empty sampler doesn't loop forever.
+        let empty = SequentialSampler::new(0).cycle();
+        assert_eq!(empty.len(), 0);
+        assert_eq!(empty.iter().next(), None);
+    }
+
+    #[test]
+    fn take() {
+        let sampler = SequentialSampler::new(10).take(4);
+        assert_eq!(sampler.len(), 4);
+        assert_eq!(sampler.iter().collect::<Vec<_>>(), vec![0, 1, 2, 3]);
+        assert_eq!(SequentialSampler::new(2).take(4).len(), 2);
+    }
+
+    #[test]
+    fn filter() {
+        let sampler = SequentialSampler::new(10).filter(|idx| idx % 3 == 0);
+        assert_eq!(sampler.len(), 10);
+        assert_eq!(sampler.iter().collect::<Vec<_>>(), vec![0, 3, 6, 9]);
+    }
+
+    #[test]
+    fn chain() {
+        let sampler = SequentialSampler::new(2).chain(SequentialSampler::new(3));
+        assert_eq!(sampler.len(), 5);
+        let mut iter = sampler.iter();
+        assert_eq!(iter.size_hint(), (5, Some(5)));
+        assert_eq!(iter.by_ref().collect::<Vec<_>>(), vec![0, 1, 0, 1, 2]);
+    }
+
+    #[test]
+    fn interleave() {
+        let sampler = SequentialSampler::new(2).interleave(SequentialSampler::new(4).take(3));
+        assert_eq!(sampler.len(), 5);
+        assert_eq!(sampler.iter().collect::<Vec<_>>(), vec![0, 0, 1, 1, 2]);
+    }
+
+    #[test]
+    fn set_epoch() {
+        let mut sampler = DistributedSampler::new(10, 2, 0)
+            .chain(DistributedSampler::new(10, 2, 1))
+            .repeat(2);
+        let epoch_0: Vec<_> = sampler.iter().collect();
+        sampler.set_epoch(1);
+        assert_ne!(sampler.iter().collect::<Vec<_>>(), epoch_0);
+    }
+
+    #[test]
+    fn batch_sampler() {
+        let batch_sampler = BatchSampler {
+            sampler: SequentialSampler::new(5).filter(|idx| *idx != 2).cycle(),
+            batch_size: 2,
+            drop_last: false,
+        };
+        // The length doesn't overflow.
+        assert_eq!(batch_sampler.len(), usize::MAX / 2 + 1);
+        let mut iter = batch_sampler.iter();
+        assert_eq!(iter.next(), Some(vec![0, 1]));
+        assert_eq!(iter.next(), Some(vec![3, 4]));
+        assert_eq!(iter.next(), Some(vec![0, 1]));
+    }
+}
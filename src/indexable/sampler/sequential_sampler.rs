@@ -2,7 +2,9 @@
 
 use std::ops::Range;
 
-use super::{Len, Sampler};
+use rand::rngs::StdRng;
+
+use super::{FromLen, Len, Sampler};
 
 /// Yield index from zero to `data_source_len` in ascending order.
 #[derive(Debug, Clone, Copy, PartialEq, PartialOrd, Hash, Eq, Ord)]
@@ -10,12 +12,27 @@ pub struct SequentialSampler {
     /// The length of the dataset that will be sampled.
     pub data_source_len: usize,
 }
-impl Sampler for SequentialSampler {
-    fn new(data_source_len: usize) -> Self {
+impl SequentialSampler {
+    /// Create a new [`SequentialSampler`] over a dataset of length `data_source_len`.
+    #[must_use]
+    pub fn new(data_source_len: usize) -> Self {
         Self { data_source_len }
     }
 }
 
+impl Sampler for SequentialSampler {
+    type Iter = Range<usize>;
+    fn iter_with_rng(&self, _rng: &mut StdRng) -> Self::Iter {
+        0..self.data_source_len
+    }
+}
+
+impl FromLen for SequentialSampler {
+    fn from_len(data_source_len: usize) -> Self {
+        Self::new(data_source_len)
+    }
+}
+
 impl Len for SequentialSampler {
     fn len(&self) -> usize {
         self.data_source_len
@@ -0,0 +1,73 @@
+//! Yield the given indices in a random order.
+
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+
+use super::{Len, Sampler};
+
+/// Yield the given indices in a random order, for instance to train on a subset of the dataset without copying it.
+///
+/// ```
+/// use ai_dataloader::sampler::{Sampler, SubsetRandomSampler};
+///
+/// let sampler = SubsetRandomSampler::new(vec![4, 2, 7]);
+/// let mut indices: Vec<_> = sampler.iter().collect();
+/// indices.sort_unstable();
+/// assert_eq!(indices, vec![2, 4, 7]);
+/// ```
+#[derive(Debug, Clone, PartialEq, PartialOrd, Hash, Eq, Ord)]
+pub struct SubsetRandomSampler {
+    /// The indices of the subset.
+    pub indices: Vec<usize>,
+}
+
+impl SubsetRandomSampler {
+    /// Create a new [`SubsetRandomSampler`] over the given indices.
+    #[must_use]
+    pub fn new(indices: Vec<usize>) -> Self {
+        Self { indices }
+    }
+}
+
+impl Len for SubsetRandomSampler {
+    fn len(&self) -> usize {
+        self.indices.len()
+    }
+}
+
+impl Sampler for SubsetRandomSampler {
+    type Iter = std::vec::IntoIter<usize>;
+    fn iter_with_rng(&self, rng: &mut StdRng) -> Self::Iter {
+        let mut indices = self.indices.clone();
+        indices.shuffle(rng);
+        indices.into_iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+
+    #[test]
+    fn subset_random_sampler() {
+        let sampler = SubsetRandomSampler::new(vec![10, 11, 12, 13, 14, 15]);
+        assert_eq!(sampler.len(), 6);
+        let mut indices: Vec<_> = sampler.iter().collect();
+        indices.sort_unstable();
+        assert_eq!(indices, sampler.indices);
+    }
+
+    #[test]
+    fn seeded() {
+        let sampler = SubsetRandomSampler::new((0..50).collect());
+        assert_eq!(
+            sampler
+                .iter_with_rng(&mut StdRng::seed_from_u64(3))
+                .collect::<Vec<_>>(),
+            sampler
+                .iter_with_rng(&mut StdRng::seed_from_u64(3))
+                .collect::<Vec<_>>()
+        );
+    }
+}
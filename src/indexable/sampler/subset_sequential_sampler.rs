@@ -0,0 +1,57 @@
+//! Yield the given indices in order.
+
+use rand::rngs::StdRng;
+
+use super::{Len, Sampler};
+
+/// Yield the given indices in order, for instance to iterate over a validation fold without copying the dataset.
+///
+/// ```
+/// use ai_dataloader::sampler::{Sampler, SubsetSequentialSampler};
+///
+/// let sampler = SubsetSequentialSampler::new(vec![4, 2, 7]);
+/// assert_eq!(sampler.iter().collect::<Vec<_>>(), vec![4, 2, 7]);
+/// ```
+#[derive(Debug, Clone, PartialEq, PartialOrd, Hash, Eq, Ord)]
+pub struct SubsetSequentialSampler {
+    /// The indices of the subset.
+    pub indices: Vec<usize>,
+}
+
+impl SubsetSequentialSampler {
+    /// Create a new [`SubsetSequentialSampler`] over the given indices.
+    #[must_use]
+    pub fn new(indices: Vec<usize>) -> Self {
+        Self { indices }
+    }
+}
+
+impl Len for SubsetSequentialSampler {
+    fn len(&self) -> usize {
+        self.indices.len()
+    }
+}
+
+impl Sampler for SubsetSequentialSampler {
+    type Iter = std::vec::IntoIter<usize>;
+    fn iter_with_rng(&self, _rng: &mut StdRng) -> Self::Iter {
+        self.indices.clone().into_iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn subset_sequential_sampler() {
+        let sampler = SubsetSequentialSampler::new(vec![3, 1, 8]);
+        assert_eq!(sampler.len(), 3);
+        let mut iter = sampler.iter();
+        assert_eq!(iter.len(), 3);
+        assert_eq!(iter.next(), Some(3));
+        assert_eq!(iter.next(), Some(1));
+        assert_eq!(iter.next(), Some(8));
+        assert_eq!(iter.next(), None);
+    }
+}
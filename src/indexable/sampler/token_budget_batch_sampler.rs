@@ -0,0 +1,238 @@
+//! Gather samples into batches of variable size, bounded by a number of tokens.
+
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+
+use super::{BatchSampling, Len};
+use crate::Dataset;
+
+/// Batch sampler that fills each batch up to a token budget, instead of using a fixed batch size.
+///
+/// It is the usual way to train models on variable length sequences: a batch holds many short samples
+/// or a few long ones. By default the cost of a batch is its size once padded, `max_len_in_batch * batch_len`,
+/// it can be changed to the sum of the lengths with [`TokenBudgetBatchSampler::sum_of_lengths`].
+///
+/// The indices are sorted by length before being gathered, so that the samples of a batch have similar lengths
+/// and the number of batches doesn't change from one epoch to another.
+/// By default the indices of the same length are shuffled, and the batches are shuffled.
+///
+/// ```
+/// use ai_dataloader::sampler::{BatchSampling, TokenBudgetBatchSampler};
+/// use ai_dataloader::Len;
+///
+/// let lengths = vec![2, 8, 3, 2, 7, 4];
+/// let sampler = TokenBudgetBatchSampler::new(&lengths, 10);
+/// for batch in sampler.iter() {
+///     let max_len = batch.iter().map(|&idx| lengths[idx]).max().unwrap();
+///     assert!(max_len * batch.len() <= 10);
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, PartialOrd, Hash, Eq, Ord)]
+pub struct TokenBudgetBatchSampler {
+    /// The length of each sample of the dataset.
+    lengths: Vec<usize>,
+    /// The maximum number of tokens in a batch.
+    max_tokens: usize,
+    /// If `true`, the cost of a batch is the sum of the lengths, otherwise it's its padded size.
+    sum_of_lengths: bool,
+    /// If `true`, the indices of the same length are shuffled, and the batches are shuffled.
+    shuffle: bool,
+    /// The number of batches, computed once as it doesn't depend on the shuffling.
+    num_batches: usize,
+}
+
+impl TokenBudgetBatchSampler {
+    /// Create a new [`TokenBudgetBatchSampler`].
+    ///
+    /// # Arguments
+    ///
+    /// * `lengths` - The length of each sample of the dataset.
+    /// * `max_tokens` - The maximum number of tokens in a batch, padding included.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a sample is longer than `max_tokens`, as it wouldn't fit in any batch.
+    #[must_use]
+    pub fn new(lengths: &[usize], max_tokens: usize) -> Self {
+        if let Some((idx, length)) = lengths
+            .iter()
+            .enumerate()
+            .find(|(_, length)| **length > max_tokens)
+        {
+            panic!(
+                "the sample {idx} of length {length} doesn't fit in a batch of {max_tokens} tokens"
+            );
+        }
+        let mut sampler = Self {
+            lengths: lengths.to_vec(),
+            max_tokens,
+            sum_of_lengths: false,
+            shuffle: true,
+            num_batches: 0,
+        };
+        sampler.num_batches = sampler.count_batches();
+        sampler
+    }
+
+    /// Create a new [`TokenBudgetBatchSampler`], computing the length of each sample of `dataset` with `length`.
+    ///
+    /// All the samples of the dataset are fetched once.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a sample is longer than `max_tokens`, as it wouldn't fit in any batch.
+    pub fn from_dataset<D, F>(dataset: &D, length: F, max_tokens: usize) -> Self
+    where
+        D: Dataset,
+        F: Fn(D::Sample) -> usize,
+    {
+        let lengths: Vec<usize> = (0..dataset.len())
+            .map(|idx| length(dataset.get_sample(idx)))
+            .collect();
+        Self::new(&lengths, max_tokens)
+    }
+
+    /// Bound the sum of the lengths of a batch by `max_tokens`, instead of its padded size.
+    ///
+    /// Use it when the samples are packed rather than padded.
+    #[must_use]
+    pub fn sum_of_lengths(mut self) -> Self {
+        self.sum_of_lengths = true;
+        self.num_batches = self.count_batches();
+        self
+    }
+
+    /// Yield the batches in ascending order of length.
+    #[must_use]
+    pub fn without_shuffle(mut self) -> Self {
+        self.shuffle = false;
+        self
+    }
+
+    /// Gather the indices, sorted by length, into batches that fit in the budget.
+    fn batches(&self, sorted_indices: &[usize]) -> Vec<Vec<usize>> {
+        let mut batches = Vec::new();
+        let mut batch: Vec<usize> = Vec::new();
+        let mut tokens = 0;
+        for &idx in sorted_indices {
+            let length = self.lengths[idx];
+            // The indices are sorted, so the new sample is the longest of the batch.
+            let cost = if self.sum_of_lengths {
+                tokens + length
+            } else {
+                length * (batch.len() + 1)
+            };
+            if cost > self.max_tokens && !batch.is_empty() {
+                batches.push(std::mem::take(&mut batch));
+                tokens = 0;
+            }
+            batch.push(idx);
+            tokens += length;
+        }
+        if !batch.is_empty() {
+            batches.push(batch);
+        }
+        batches
+    }
+
+    /// Count the batches, gathering the indices sorted by length, the ties being in ascending order.
+    fn count_batches(&self) -> usize {
+        let mut indices: Vec<usize> = (0..self.lengths.len()).collect();
+        indices.sort_by_key(|idx| self.lengths[*idx]);
+        self.batches(&indices).len()
+    }
+}
+
+impl Len for TokenBudgetBatchSampler {
+    /// Returns the number of batch.
+    ///
+    /// It doesn't depend on the shuffling, as only the lengths of the samples are taken into account.
+    fn len(&self) -> usize {
+        self.num_batches
+    }
+}
+
+impl BatchSampling for TokenBudgetBatchSampler {
+    type Iter = std::vec::IntoIter<Vec<usize>>;
+    fn iter_with_rng(&self, rng: &mut StdRng) -> Self::Iter {
+        let mut indices: Vec<usize> = (0..self.lengths.len()).collect();
+        if self.shuffle {
+            indices.shuffle(rng);
+        }
+        // The sort is stable: the samples of the same length stay shuffled.
+        indices.sort_by_key(|idx| self.lengths[*idx]);
+        let mut batches = self.batches(&indices);
+        if self.shuffle {
+            batches.shuffle(rng);
+        }
+        batches.into_iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn token_budget_batch_sampler() {
+        let lengths = vec![5, 1, 9, 3, 2, 6, 1, 4, 8, 3];
+        let sampler = TokenBudgetBatchSampler::new(&lengths, 10);
+        let batches: Vec<_> = sampler.iter().collect();
+        assert_eq!(batches.len(), sampler.len());
+        for batch in &batches {
+            let max_len = batch.iter().map(|idx| lengths[*idx]).max().unwrap();
+            assert!(max_len * batch.len() <= 10);
+        }
+        let mut indices = batches.concat();
+        indices.sort_unstable();
+        assert_eq!(indices, (0..lengths.len()).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn without_shuffle() {
+        let lengths = vec![5, 1, 9, 3, 2, 6, 1, 4, 8, 3];
+        let sampler = TokenBudgetBatchSampler::new(&lengths, 10).without_shuffle();
+        assert_eq!(sampler.len(), 6);
+        assert_eq!(
+            sampler.iter().collect::<Vec<_>>(),
+            vec![
+                vec![1, 6, 4],
+                vec![3, 9],
+                vec![7, 0],
+                vec![5],
+                vec![8],
+                vec![2]
+            ]
+        );
+    }
+
+    #[test]
+    fn sum_of_lengths() {
+        let lengths = vec![5, 1, 9, 3, 2, 6, 1, 4, 8, 3];
+        let sampler = TokenBudgetBatchSampler::new(&lengths, 10)
+            .sum_of_lengths()
+            .without_shuffle();
+        assert_eq!(sampler.len(), 5);
+        assert_eq!(
+            sampler.iter().collect::<Vec<_>>(),
+            vec![vec![1, 6, 4, 3, 9], vec![7, 0], vec![5], vec![8], vec![2]]
+        );
+    }
+
+    #[test]
+    fn from_dataset() {
+        let dataset = vec![String::from("abc"), String::from("a"), String::from("ab")];
+        let sampler =
+            TokenBudgetBatchSampler::from_dataset(&dataset, |text| text.len(), 4).without_shuffle();
+        assert_eq!(
+            sampler.iter().collect::<Vec<_>>(),
+            vec![vec![1, 2], vec![0]]
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "the sample 1 of length 12 doesn't fit in a batch of 10 tokens")]
+    fn sample_too_long() {
+        let _sampler = TokenBudgetBatchSampler::new(&[3, 12], 10);
+    }
+}
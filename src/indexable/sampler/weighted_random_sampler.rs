@@ -0,0 +1,215 @@
+//! Yield index from zero to `weights.len()` with the given probabilities (weights).
+
+use rand::distr::{weighted::WeightedIndex, Distribution};
+use rand::rngs::StdRng;
+use rand::Rng;
+
+use super::{Len, Sampler};
+
+/// Sampler that draws `num_samples` indices from zero to `weights.len()` with the given probabilities (weights).
+///
+/// The weights don't need to sum to one. It's typically used on imbalanced datasets to draw the rare classes
+/// as often as the common ones, by giving to each index a weight inversely proportional to the frequency of its class.
+///
+/// By default the indices are drawn with replacement, like in `PyTorch`.
+///
+/// ```
+/// use ai_dataloader::sampler::{Sampler, WeightedRandomSampler};
+/// use ai_dataloader::Len;
+///
+/// let sampler = WeightedRandomSampler::new(vec![0.1, 0.9, 0.0], 5);
+/// assert_eq!(sampler.len(), 5);
+/// assert!(sampler.iter().all(|idx| idx < 2));
+/// ```
+#[derive(Debug, Clone, PartialEq, PartialOrd)]
+pub struct WeightedRandomSampler {
+    /// The weight of each index of the dataset.
+    weights: Vec<f64>,
+    /// The number of indices to draw at each iteration.
+    num_samples: usize,
+    /// Whether the sample is replaced or not.
+    /// If it's replaced, we can have 2 times the same sample.
+    replacement: bool,
+}
+
+impl WeightedRandomSampler {
+    /// Create a new [`WeightedRandomSampler`] drawing `num_samples` indices with replacement.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there are no weights, if a weight is negative or not finite, or if all the weights are zero.
+    #[must_use]
+    pub fn new(weights: Vec<f64>, num_samples: usize) -> Self {
+        assert!(!weights.is_empty(), "weights should not be empty");
+        assert!(
+            weights
+                .iter()
+                .all(|weight| weight.is_finite() && *weight >= 0.),
+            "weights should be finite and non-negative"
+        );
+        assert!(
+            weights.iter().any(|weight| *weight > 0.),
+            "at least one weight should be positive"
+        );
+        Self {
+            weights,
+            num_samples,
+            replacement: true,
+        }
+    }
+
+    /// Draw the indices without replacement: each index is yielded at most once per iteration.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `num_samples` is greater than the number of indices with a positive weight.
+    #[must_use]
+    pub fn without_replacement(mut self) -> Self {
+        let num_candidates = self.weights.iter().filter(|weight| **weight > 0.).count();
+        assert!(
+            self.num_samples <= num_candidates,
+            "cannot draw {} samples without replacement among {num_candidates} indices with a positive weight",
+            self.num_samples
+        );
+        self.replacement = false;
+        self
+    }
+}
+
+impl Sampler for WeightedRandomSampler {
+    type Iter = WeightedRandomSamplerIter;
+    fn iter_with_rng(&self, rng: &mut StdRng) -> Self::Iter {
+        WeightedRandomSamplerIter::new(self, rng)
+    }
+}
+
+impl Len for WeightedRandomSampler {
+    fn len(&self) -> usize {
+        self.num_samples
+    }
+}
+
+/// Iterator that returns the indices drawn by a [`WeightedRandomSampler`].
+#[derive(Debug)]
+pub struct WeightedRandomSamplerIter {
+    /// The indices drawn for this iteration.
+    indexes: Vec<usize>,
+    /// The current index.
+    idx: usize,
+}
+
+impl WeightedRandomSamplerIter {
+    fn new<R: Rng + ?Sized>(sampler: &WeightedRandomSampler, rng: &mut R) -> Self {
+        let indexes = if sampler.replacement {
+            let distribution =
+                WeightedIndex::new(&sampler.weights).expect("weights are validated on creation");
+            distribution
+                .sample_iter(rng)
+                .take(sampler.num_samples)
+                .collect()
+        } else {
+            // Efraimidis-Spirakis algorithm: each index gets the key `u^(1 / weight)`, with `u` uniform in (0, 1].
+            // Sorting the keys in descending order gives a weighted permutation.
+            // The keys are compared in log space for numerical stability.
+            let mut keys: Vec<(f64, usize)> = sampler
+                .weights
+                .iter()
+                .enumerate()
+                .filter(|(_, weight)| **weight > 0.)
+                .map(|(idx, weight)| ((1. - rng.random::<f64>()).ln() / weight, idx))
+                .collect();
+            keys.sort_unstable_by(|a, b| b.0.total_cmp(&a.0));
+            keys.into_iter()
+                .take(sampler.num_samples)
+                .map(|(_, idx)| idx)
+                .collect()
+        };
+        Self { indexes, idx: 0 }
+    }
+}
+
+impl Iterator for WeightedRandomSamplerIter {
+    type Item = usize;
+    fn next(&mut self) -> Option<Self::Item> {
+        let idx = self.indexes.get(self.idx).copied();
+        if idx.is_some() {
+            self.idx += 1;
+        }
+        idx
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.indexes.len() - self.idx;
+        (len, Some(len))
+    }
+}
+
+impl ExactSizeIterator for WeightedRandomSamplerIter {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use itertools::Itertools;
+    use rand::SeedableRng;
+
+    #[test]
+    fn weighted_random_sampler() {
+        let sampler = WeightedRandomSampler::new(vec![0., 1., 0., 3.], 1000);
+        assert_eq!(sampler.len(), 1000);
+        let counts = sampler.iter().counts();
+        assert_eq!(counts.get(&0), None);
+        assert_eq!(counts.get(&2), None);
+        assert_eq!(counts[&1] + counts[&3], 1000);
+        // The index 3 is three times more likely than the index 1.
+        assert!(counts[&3] > counts[&1]);
+    }
+
+    #[test]
+    fn without_replacement() {
+        let sampler = WeightedRandomSampler::new(vec![1., 0., 2., 5., 1.], 4).without_replacement();
+        let indices: Vec<_> = sampler.iter().collect();
+        assert_eq!(indices.len(), 4);
+        assert!(indices.iter().all_unique());
+        assert!(!indices.contains(&1));
+    }
+
+    #[test]
+    #[should_panic(expected = "cannot draw 3 samples without replacement")]
+    fn without_replacement_too_many_samples() {
+        let _sampler = WeightedRandomSampler::new(vec![1., 0., 2.], 3).without_replacement();
+    }
+
+    #[test]
+    #[should_panic(expected = "weights should be finite and non-negative")]
+    fn negative_weight() {
+        let _sampler = WeightedRandomSampler::new(vec![1., -1.], 3);
+    }
+
+    #[test]
+    #[should_panic(expected = "weights should not be empty")]
+    fn empty_weights() {
+        let _sampler = WeightedRandomSampler::new(Vec::new(), 3);
+    }
+
+    #[test]
+    fn seeded() {
+        let sampler = WeightedRandomSampler::new(vec![1., 2., 3., 4.], 20);
+        assert_eq!(
+            sampler
+                .iter_with_rng(&mut StdRng::seed_from_u64(42))
+                .collect::<Vec<_>>(),
+            sampler
+                .iter_with_rng(&mut StdRng::seed_from_u64(42))
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn len() {
+        let sampler = WeightedRandomSampler::new(vec![1., 2., 3., 4.], 3);
+        let mut iter = sampler.iter();
+        assert_eq!(iter.len(), 3);
+        iter.next();
+        assert_eq!(iter.len(), 2);
+    }
+}
@@ -1,4 +1,4 @@
-//! # Indexable `Dataloader`.
+//! # Iterable `Dataloader`.
 
 mod dataloader;
-pub use dataloader::DataLoader;
+pub use dataloader::{DataLoader, DataLoaderState, IntoIter, Iter};
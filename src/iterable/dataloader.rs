@@ -5,12 +5,19 @@
 
 mod builder;
 use builder::Builder;
-use rand::{rng, seq::SliceRandom};
+use rand::{seq::SliceRandom, Rng};
+use serde::{Deserialize, Serialize};
 
-use crate::collate::{Collate, DefaultCollate};
+use crate::{
+    collate::{Collate, DefaultCollate},
+    rng_from_words,
+};
 
 /// For iterable dataset, the `datalaoder` will yield until the underlying iterator is `None`.
 /// As the iteration over the dataset can be done multiple time, depending if the underlying dataset iterator consume the dataset or not.
+///
+/// Its iterators panic if the samples can't be collated, only the indexable
+/// [`DataLoader::try_iter`](crate::indexable::DataLoader::try_iter) returns the collate errors.
 #[derive(Debug)]
 pub struct DataLoader<D, C> {
     /// The dataset we will iterate over.
@@ -24,6 +31,27 @@ pub struct DataLoader<D, C> {
     collate_fn: C,
     /// If `true` the sample in the batch will be shuffled
     shuffle: bool,
+    /// Seed of the random number generator used for shuffling.
+    seed: Option<u64>,
+    /// Current epoch, mixed with the seed to shuffle differently at each epoch.
+    epoch: u64,
+    /// State loaded with [`DataLoader::load_state_dict`], the iterations of its epoch resume from it.
+    resume_state: Option<DataLoaderState>,
+}
+
+/// Progress of an iteration over a [`DataLoader`], to checkpoint it and resume it after an interruption.
+///
+/// It is returned by the `state_dict` method of the iterators and restored with [`DataLoader::load_state_dict`].
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd, Hash, Eq, Ord, Serialize, Deserialize)]
+pub struct DataLoaderState {
+    /// The epoch of the iteration.
+    pub epoch: u64,
+    /// The seed of the random number generator used for shuffling.
+    ///
+    /// If the loader has no seed, a seed is drawn from the system entropy at the beginning of each iteration.
+    pub seed: u64,
+    /// Number of sample consumed from the dataset iterator, including the ones dropped by `drop_last`.
+    pub num_consumed: u64,
 }
 
 impl<D> DataLoader<D, DefaultCollate>
@@ -37,51 +65,91 @@ where
     }
 }
 
-// we want to use dataloader in for loop
-// A dataset is something we can turn into an iterator.
-// We make a an iterator that consume this iterator and yield only batches of it.
-impl<D, C> IntoIterator for DataLoader<D, C>
-where
-    D: IntoIterator,
-    C: Collate<<D as IntoIterator>::Item>,
-{
-    // We yield batch of dataset element (which can be transformed by the collate function).
-    type Item = C::Output;
-    type IntoIter = IntoIter<D::IntoIter, C>;
+impl<D, C> DataLoader<D, C> {
+    /// Set the current epoch, to call at the beginning of each epoch.
+    ///
+    /// With a seed, the batches are shuffled differently at each epoch, in a reproducible way.
+    pub fn set_epoch(&mut self, epoch: u64) {
+        self.epoch = epoch;
+    }
 
-    fn into_iter(self) -> Self::IntoIter {
-        IntoIter {
-            batch_size: self.batch_size,
-            dataset_iter: self.dataset.into_iter(),
-            drop_last: self.drop_last,
-            collate_fn: self.collate_fn,
-            shuffle: self.shuffle,
+    /// The current epoch.
+    pub fn epoch(&self) -> u64 {
+        self.epoch
+    }
+
+    /// Restore a checkpointed iteration: set the epoch of `state`, and make the iterations over this epoch
+    /// start after the samples already consumed.
+    ///
+    /// The samples are skipped with [`Iterator::nth`]. A dataset iterator that can seek, for instance in a file
+    /// or across shards, should implement it so that the skipped samples are not read.
+    /// Once another epoch is set with [`DataLoader::set_epoch`], the iterations start from the beginning again.
+    ///
+    /// ```
+    /// use ai_dataloader::iterable::DataLoader;
+    ///
+    /// let mut loader = DataLoader::builder(vec![1, 2, 3, 4, 5, 6]).batch_size(2).shuffle().build();
+    /// let mut iter = loader.iter();
+    /// iter.next();
+    /// // Checkpoint after the first batch, the state can be saved with any serde format.
+    /// let state = iter.state_dict();
+    /// let second = iter.next();
+    ///
+    /// loader.load_state_dict(state);
+    /// assert_eq!(loader.iter().next(), second);
+    /// ```
+    pub fn load_state_dict(&mut self, state: DataLoaderState) {
+        self.set_epoch(state.epoch);
+        self.resume_state = Some(state);
+    }
+
+    /// The state at the beginning of a new iteration.
+    fn start_state(&self) -> DataLoaderState {
+        match self.resume_state {
+            Some(state) if state.epoch == self.epoch => state,
+            _ => DataLoaderState {
+                epoch: self.epoch,
+                // The seed is always known, so that the iteration can be resumed.
+                seed: self.seed.unwrap_or_else(|| rand::rng().random()),
+                num_consumed: 0,
+            },
         }
     }
 }
 
-/// Iterator returned by `into_iter` function.
+/// Gather the samples of the dataset iterator into batches, keeping track of the progress.
 #[derive(Debug)]
-pub struct IntoIter<D, C> {
-    batch_size: usize,
+struct Batches<D> {
     dataset_iter: D,
+    batch_size: usize,
     drop_last: bool,
-    collate_fn: C,
     shuffle: bool,
+    state: DataLoaderState,
 }
 
-impl<D, C> Iterator for IntoIter<D, C>
-where
-    D: Iterator,
-    C: Collate<D::Item>,
-{
-    type Item = C::Output;
+impl<D: Iterator> Batches<D> {
+    /// Skip the samples already consumed according to the state.
+    fn skip_consumed(mut self) -> Self {
+        if self.state.num_consumed > 0 {
+            let num_consumed = usize::try_from(self.state.num_consumed)
+                .expect("the number of sample consumed should fit in usize");
+            self.dataset_iter.nth(num_consumed - 1);
+        }
+        self
+    }
+}
+
+impl<D: Iterator> Iterator for Batches<D> {
+    type Item = Vec<D::Item>;
     fn next(&mut self) -> Option<Self::Item> {
+        // The batch index only depends on the progress, so that a resumed iteration shuffles the same way.
+        let batch_idx = self.state.num_consumed / self.batch_size as u64;
         let mut batch = self
             .dataset_iter
             .by_ref()
             .take(self.batch_size)
             .collect::<Vec<_>>();
+        self.state.num_consumed += batch.len() as u64;
 
         if batch.is_empty() {
             return None;
@@ -89,9 +157,13 @@ where
 
         if batch.len() == self.batch_size || (batch.len() != self.batch_size && !self.drop_last) {
             if self.shuffle {
-                batch.shuffle(&mut rng());
+                batch.shuffle(&mut rng_from_words(&[
+                    self.state.seed,
+                    self.state.epoch,
+                    batch_idx,
+                ]));
             }
-            return Some(self.collate_fn.collate(batch));
+            return Some(batch);
         }
         None
     }
@@ -106,6 +178,64 @@ where
     }
 }
 
+// we want to use dataloader in for loop
+// A dataset is something we can turn into an iterator.
+// We make a an iterator that consume this iterator and yield only batches of it.
+impl<D, C> IntoIterator for DataLoader<D, C>
+where
+    D: IntoIterator,
+    C: Collate<<D as IntoIterator>::Item>,
+{
+    // We yield batch of dataset element (which can be transformed by the collate function).
+    type Item = C::Output;
+    type IntoIter = IntoIter<D::IntoIter, C>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        let state = self.start_state();
+        IntoIter {
+            batches: Batches {
+                dataset_iter: self.dataset.into_iter(),
+                batch_size: self.batch_size,
+                drop_last: self.drop_last,
+                shuffle: self.shuffle,
+                state,
+            }
+            .skip_consumed(),
+            collate_fn: self.collate_fn,
+        }
+    }
+}
+
+/// Iterator returned by `into_iter` function.
+#[derive(Debug)]
+pub struct IntoIter<D, C> {
+    batches: Batches<D>,
+    collate_fn: C,
+}
+
+impl<D, C> IntoIter<D, C> {
+    /// Return the progress of this iteration, to resume it later with [`DataLoader::load_state_dict`].
+    pub fn state_dict(&self) -> DataLoaderState {
+        self.batches.state
+    }
+}
+
+impl<D, C> Iterator for IntoIter<D, C>
+where
+    D: Iterator,
+    C: Collate<D::Item>,
+{
+    type Item = C::Output;
+    fn next(&mut self) -> Option<Self::Item> {
+        self.batches
+            .next()
+            .map(|batch| self.collate_fn.collate(batch))
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.batches.size_hint()
+    }
+}
+
 impl<D, C> ExactSizeIterator for IntoIter<D, C>
 where
     D: Iterator + ExactSizeIterator,
@@ -116,12 +246,15 @@ where
 /// Iterator returned by `iter` function.
 #[derive(Debug)]
 pub struct Iter<'dataset, D, C> {
-    batch_size: usize,
-    #[allow(clippy::struct_field_names)]
-    dataset_iter: D,
-    drop_last: bool,
+    batches: Batches<D>,
     collate_fn: &'dataset C,
-    shuffle: bool,
+}
+
+impl<D, C> Iter<'_, D, C> {
+    /// Return the progress of this iteration, to resume it later with [`DataLoader::load_state_dict`].
+    pub fn state_dict(&self) -> DataLoaderState {
+        self.batches.state
+    }
 }
 
 impl<'dataset, D, C> IntoIterator for &'dataset DataLoader<D, C>
@@ -134,13 +267,7 @@ where
     type IntoIter = Iter<'dataset, <&'dataset D as IntoIterator>::IntoIter, C>;
 
     fn into_iter(self) -> Self::IntoIter {
-        Iter {
-            batch_size: self.batch_size,
-            dataset_iter: self.dataset.into_iter(),
-            drop_last: self.drop_last,
-            collate_fn: &self.collate_fn,
-            shuffle: self.shuffle,
-        }
+        self.iter()
     }
 }
 
@@ -154,39 +281,32 @@ where
     /// As it make no sens to collate reference into a tensor, by default element are copied.
     pub fn iter(&'dataset self) -> Iter<'dataset, <&'dataset D as IntoIterator>::IntoIter, C> {
         Iter {
-            batch_size: self.batch_size,
-            dataset_iter: self.dataset.into_iter(),
-            drop_last: self.drop_last,
+            batches: Batches {
+                dataset_iter: self.dataset.into_iter(),
+                batch_size: self.batch_size,
+                drop_last: self.drop_last,
+                shuffle: self.shuffle,
+                state: self.start_state(),
+            }
+            .skip_consumed(),
             collate_fn: &self.collate_fn,
-            shuffle: self.shuffle,
         }
     }
 }
 
-impl<'dataset, D, C> Iterator for Iter<'dataset, D, C>
+impl<D, C> Iterator for Iter<'_, D, C>
 where
     D: Iterator,
     C: Collate<D::Item>,
 {
     type Item = C::Output;
     fn next(&mut self) -> Option<Self::Item> {
-        let mut batch = self
-            .dataset_iter
-            .by_ref()
-            .take(self.batch_size)
-            .collect::<Vec<_>>();
-
-        if batch.is_empty() {
-            return None;
-        }
-
-        if batch.len() == self.batch_size || (batch.len() != self.batch_size && !self.drop_last) {
-            if self.shuffle {
-                batch.shuffle(&mut rng());
-            }
-            return Some(self.collate_fn.collate(batch));
-        }
-        None
+        self.batches
+            .next()
+            .map(|batch| self.collate_fn.collate(batch))
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.batches.size_hint()
     }
 }
 
@@ -220,6 +340,105 @@ mod tests {
         assert_eq!(into_iter.next(), None);
     }
 
+    #[test]
+    fn seed() {
+        let dataset: Vec<_> = (0..100).collect();
+        let loader = DataLoader::builder(dataset.clone())
+            .batch_size(10)
+            .shuffle()
+            .seed(42)
+            .build();
+        let first: Vec<_> = loader.iter().collect();
+        assert_eq!(loader.iter().collect::<Vec<_>>(), first);
+
+        let loader = DataLoader::builder(dataset)
+            .batch_size(10)
+            .shuffle()
+            .seed(42)
+            .build();
+        assert_eq!(loader.into_iter().collect::<Vec<_>>(), first);
+    }
+
+    #[test]
+    fn set_epoch() {
+        let mut loader = DataLoader::builder((0..100).collect::<Vec<_>>())
+            .batch_size(10)
+            .shuffle()
+            .seed(42)
+            .build();
+        let epoch_0: Vec<_> = loader.iter().collect();
+        loader.set_epoch(1);
+        assert_eq!(loader.epoch(), 1);
+        let epoch_1: Vec<_> = loader.iter().collect();
+        assert_ne!(epoch_0, epoch_1);
+        loader.set_epoch(0);
+        assert_eq!(loader.iter().collect::<Vec<_>>(), epoch_0);
+    }
+
+    #[test]
+    fn state_dict() {
+        let dataset: Vec<_> = (0..25).collect();
+        let mut loader = DataLoader::builder(dataset).batch_size(4).shuffle().build();
+        loader.set_epoch(3);
+        let mut iter = loader.iter();
+        iter.next();
+        iter.next();
+        let state = iter.state_dict();
+        assert_eq!(state.epoch, 3);
+        assert_eq!(state.num_consumed, 8);
+        let remaining: Vec<_> = iter.collect();
+
+        let checkpoint = serde_json::to_string(&state).unwrap();
+        loader.load_state_dict(serde_json::from_str(&checkpoint).unwrap());
+        assert_eq!(loader.iter().collect::<Vec<_>>(), remaining);
+        let mut into_iter = loader.into_iter();
+        assert_eq!(into_iter.len(), 5);
+        assert_eq!(into_iter.by_ref().collect::<Vec<_>>(), remaining);
+        assert_eq!(into_iter.state_dict().num_consumed, 25);
+    }
+
+    #[test]
+    fn skip_with_nth() {
+        use std::cell::Cell;
+        use std::rc::Rc;
+
+        /// Records that can be read one by one, or seeked to.
+        struct Records {
+            position: usize,
+            reads: Rc<Cell<usize>>,
+        }
+        impl Iterator for Records {
+            type Item = usize;
+            fn next(&mut self) -> Option<Self::Item> {
+                self.reads.set(self.reads.get() + 1);
+                self.position += 1;
+                (self.position <= 10).then_some(self.position - 1)
+            }
+            fn nth(&mut self, n: usize) -> Option<Self::Item> {
+                self.position += n;
+                self.next()
+            }
+        }
+
+        let reads = Rc::new(Cell::new(0));
+        let mut loader = DataLoader::builder(Records {
+            position: 0,
+            reads: Rc::clone(&reads),
+        })
+        .batch_size(2)
+        .collate_fn(NoOpCollate)
+        .build();
+        loader.load_state_dict(DataLoaderState {
+            epoch: 0,
+            seed: 0,
+            num_consumed: 6,
+        });
+        let mut into_iter = loader.into_iter();
+        assert_eq!(into_iter.next(), Some(vec![6, 7]));
+        // One read to reach the position 6, then two for the batch.
+        assert_eq!(reads.get(), 3);
+    }
+
     #[test]
     fn drop_last() {
         let dataset = vec![0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10];
@@ -20,6 +20,8 @@ where
     collate_fn: C,
 
     shuffle: bool,
+    /// Seed of the random number generator used for shuffling.
+    seed: Option<u64>,
 }
 
 impl<D> Builder<D, DefaultCollate>
@@ -36,6 +38,7 @@ where
             drop_last: false,
             collate_fn: DefaultCollate,
             shuffle: false,
+            seed: None,
         }
     }
 }
@@ -50,6 +53,11 @@ where
         self.shuffle = true;
         self
     }
+    /// Seed the random number generator used for shuffling, making the order of the samples in a batch reproducible.
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
     /// Set the number of elements in a batch.
     pub fn batch_size(mut self, batch_size: usize) -> Self {
         self.batch_size = batch_size;
@@ -74,6 +82,7 @@ where
             drop_last: self.drop_last,
             collate_fn,
             shuffle: self.shuffle,
+            seed: self.seed,
         }
     }
 
@@ -85,6 +94,9 @@ where
             drop_last: self.drop_last,
             collate_fn: self.collate_fn,
             shuffle: self.shuffle,
+            seed: self.seed,
+            epoch: 0,
+            resume_state: None,
         }
     }
 }
@@ -126,5 +138,7 @@ mod tests {
             .drop_last()
             .collate_fn(|x| x)
             .build();
+
+        let _loader = Builder::new(vec![1, 2, 3, 4]).shuffle().seed(42).build();
     }
 }
@@ -12,7 +12,7 @@
 //! - Customizable `Sampler`, `BatchSampler` and `collate_fn`.
 //! - Integration with [`ndarray`] and [`tch-rs`], CPU and GPU support.
 //! - Default collate function that will automatically collate most of your type (supporting nesting).
-//! - Shuffling for iterable and indexable `DataLoader`.
+//! - Shuffling for iterable and indexable `DataLoader`, reproducible with a seed.
 //!
 //! ## Examples
 //!
@@ -27,7 +27,9 @@
 //! `DataLoader(dataset)` | `DataLoader::builder(dataset).build()` | Create a `DataLoader` with default parameters
 //! `DataLoader(dataset, batch_size=2)` | `DataLoader::builder(dataset).batch_size(2).build()` | Setup the batch size
 //! `DataLoader(dataset, shuffle=True)` | `DataLoader::builder(dataset).shuffle().build()` | Shuffle the data
-//! `DataLoader(dataset, sampler=CustomSampler)` | `DataLoader::builder(dataset).sampler::<CustomSampler>().build()` | Provide a custom sampler
+//! `DataLoader(dataset, sampler=CustomSampler(...))` | `DataLoader::builder(dataset).with_sampler(CustomSampler::new(...)).build()` | Provide a custom sampler
+//! `DataLoader(dataset, batch_sampler=CustomBatchSampler(...))` | `DataLoader::builder(dataset).with_batch_sampler(CustomBatchSampler::new(...)).build()` | Provide a custom batch sampler
+//! `DataLoader(dataset, shuffle=True, generator=torch.Generator().manual_seed(42))` | `DataLoader::builder(dataset).shuffle().seed(42).build()` | Reproducible shuffling
 //!
 //! ### Combined options
 //!
@@ -40,6 +42,7 @@
 //! `PyTorch` | `ai-dataloader` | Notes
 //! --------|-----------------|-------
 //! `for text, label in data_loader:` | `for (text, label) in data_loader.iter()` | Simple iteration
+//! `sampler.set_epoch(epoch)` | `data_loader.set_epoch(epoch)` | New permutation at each epoch
 //!
 //!
 //! ## Choosing between Iterable or Indexable dataloader
@@ -72,13 +75,71 @@ pub mod collate;
 pub mod indexable;
 pub mod iterable;
 
-pub use indexable::{sampler, Dataset, GetSample, Len, NdarrayDataset};
+pub use indexable::{
+    sampler, Dataset, GetSample, GetSampleRef, Len, NdarrayDataset, Ndarrays, TryGetSample,
+};
+
+use rand::{rngs::StdRng, SeedableRng};
 
-#[cfg(feature = "rayon")]
-use once_cell::sync::OnceCell;
 #[cfg(feature = "rayon")]
 use rayon::ThreadPool;
+#[cfg(feature = "rayon")]
+use std::{cmp::Ordering, hash::Hash, sync::Arc};
+
+/// Thread pool of a dataloader, shared with its clones and with the other loaders it was given to.
+///
+/// Pools are compared by identity, so that the loaders can still be compared and hashed.
+#[cfg(feature = "rayon")]
+#[derive(Debug, Clone)]
+pub(crate) struct SharedThreadPool(pub(crate) Arc<ThreadPool>);
+
+#[cfg(feature = "rayon")]
+impl PartialEq for SharedThreadPool {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
 
-/// Thread pool used by the dataloader.
 #[cfg(feature = "rayon")]
-pub static THREAD_POOL: OnceCell<ThreadPool> = OnceCell::new();
+impl Eq for SharedThreadPool {}
+
+#[cfg(feature = "rayon")]
+impl PartialOrd for SharedThreadPool {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+#[cfg(feature = "rayon")]
+impl Ord for SharedThreadPool {
+    fn cmp(&self, other: &Self) -> Ordering {
+        Arc::as_ptr(&self.0).cmp(&Arc::as_ptr(&other.0))
+    }
+}
+
+#[cfg(feature = "rayon")]
+impl Hash for SharedThreadPool {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        Arc::as_ptr(&self.0).hash(state);
+    }
+}
+
+/// Create the random number generator of a `DataLoader` iteration.
+///
+/// If a seed is given, the generator is deterministic and differs for each epoch,
+/// otherwise it's seeded from the system entropy.
+pub(crate) fn rng_from_seed(seed: Option<u64>, epoch: u64) -> StdRng {
+    match seed {
+        Some(seed) => rng_from_words(&[seed, epoch]),
+        None => StdRng::from_rng(&mut rand::rng()),
+    }
+}
+
+/// Create a deterministic random number generator from up to four words, such as a seed, an epoch and a batch index.
+pub(crate) fn rng_from_words(words: &[u64]) -> StdRng {
+    let mut bytes = <StdRng as SeedableRng>::Seed::default();
+    for (chunk, word) in bytes.chunks_exact_mut(8).zip(words) {
+        chunk.copy_from_slice(&word.to_le_bytes());
+    }
+    StdRng::from_seed(bytes)
+}